@@ -0,0 +1,1130 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use tracknft::program::Tracknft;
+use tracknft::{RoyaltyAccount, Track};
+
+declare_id!("AQ2zXikPKhLsAKLgUDY1j2asJnkcekE9omjv9SkULxqU");
+
+// Доли наследников в базисных пунктах (1 bps = 0.01%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_HEIRS: usize = 10;
+
+// Ограничения длины строк в аккаунтах
+pub const MAX_NAME_LEN: usize = 64;
+pub const MAX_CID_LEN: usize = 64;
+pub const MAX_TRIBUTE_LEN: usize = 280;
+
+pub const MEMORIAL_ACCOUNT_SPACE: usize = 8
+    + 32 + 32                          // artist, manager
+    + (4 + MAX_NAME_LEN) * 2           // artist_name, real_name
+    + 8 + 8                            // birth_date, death_date
+    + (4 + MAX_CID_LEN) * 5            // bio, avatar, banner, last_mix, last_track
+    + 1 + 1                            // memorial_type, status
+    + 4 + MAX_HEIRS * (32 + 2 + 8)     // heirs
+    + 4 + 8 + 8 + 8                    // catalog totals
+    + 8 + 8 + 8 + 8 + 8                // donations, tributes, visitors, distributed
+    + 8 + 8                            // created_at, updated_at
+    + 1 + 1;                           // bump, vault_bump
+
+#[program]
+pub mod memorial {
+    use super::*;
+
+    // Инициализация программы мемориалов
+    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+        let memorial_state = &mut ctx.accounts.memorial_state;
+        
+        memorial_state.authority = ctx.accounts.authority.key();
+        memorial_state.ndt_mint = ctx.accounts.ndt_mint.key();
+        memorial_state.total_memorials = 0;
+        memorial_state.total_donations = 0;
+        
+        Ok(())
+    }
+
+    // Создание мемориала ушедшего артиста (один на кошелек артиста).
+    // Создает только администратор платформы после проверки документов семьи.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_memorial(
+        ctx: Context<CreateMemorial>,
+        artist: Pubkey,
+        manager: Pubkey,
+        artist_name: String,
+        real_name: String,
+        birth_date: i64,
+        death_date: i64,
+        bio_cid: String,
+        avatar_cid: String,
+        memorial_type: MemorialType,
+    ) -> Result<()> {
+        let memorial_state = &mut ctx.accounts.memorial_state;
+        let memorial = &mut ctx.accounts.memorial;
+        
+        require!(
+            ctx.accounts.authority.key() == memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(manager != Pubkey::default(), ErrorCode::InvalidMemorialData);
+        require!(!artist_name.is_empty() && death_date > 0, ErrorCode::InvalidMemorialData);
+        require!(birth_date < death_date, ErrorCode::InvalidMemorialData);
+        require!(
+            artist_name.len() <= MAX_NAME_LEN && real_name.len() <= MAX_NAME_LEN,
+            ErrorCode::NameTooLong
+        );
+        require!(
+            bio_cid.len() <= MAX_CID_LEN && avatar_cid.len() <= MAX_CID_LEN,
+            ErrorCode::NameTooLong
+        );
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        memorial.artist = artist;
+        memorial.manager = manager;
+        memorial.artist_name = artist_name;
+        memorial.real_name = real_name;
+        memorial.birth_date = birth_date;
+        memorial.death_date = death_date;
+        memorial.bio_cid = bio_cid;
+        memorial.avatar_cid = avatar_cid;
+        memorial.banner_cid = String::new();
+        memorial.last_mix_cid = String::new();
+        memorial.last_track_cid = String::new();
+        memorial.memorial_type = memorial_type;
+        memorial.status = MemorialStatus::Pending;
+        memorial.heirs = Vec::new();
+        memorial.total_tracks = 0;
+        memorial.total_plays = 0;
+        memorial.total_likes = 0;
+        memorial.total_sales = 0;
+        memorial.total_donations = 0;
+        memorial.donation_count = 0;
+        memorial.tribute_count = 0;
+        memorial.visitors = 0;
+        memorial.total_distributed = 0;
+        memorial.created_at = current_time;
+        memorial.updated_at = current_time;
+        memorial.bump = ctx.bumps.memorial;
+        memorial.vault_bump = ctx.bumps.vault;
+        
+        memorial_state.total_memorials = memorial_state.total_memorials.checked_add(1).unwrap();
+        
+        emit!(MemorialCreatedEvent {
+            memorial: memorial.key(),
+            artist,
+            manager,
+            memorial_type,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Передача управления мемориалом другому представителю семьи
+    pub fn reassign_manager(ctx: Context<SetMemorialStatus>, new_manager: Pubkey) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(new_manager != Pubkey::default(), ErrorCode::InvalidMemorialData);
+        
+        let previous_manager = memorial.manager;
+        memorial.manager = new_manager;
+        memorial.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(ManagerReassignedEvent {
+            memorial: memorial.key(),
+            previous_manager,
+            new_manager,
+            timestamp: memorial.updated_at,
+        });
+        
+        Ok(())
+    }
+
+    // Закрытие ошибочного мемориала администратором (фонд должен быть распределен).
+    // Рента мемориала и хранилища возвращается администратору.
+    pub fn close_memorial(ctx: Context<CloseMemorial>) -> Result<()> {
+        let memorial_state = &mut ctx.accounts.memorial_state;
+        let memorial = &ctx.accounts.memorial;
+        
+        require!(
+            ctx.accounts.authority.key() == memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(ctx.accounts.vault.amount == 0, ErrorCode::FundNotEmpty);
+        
+        let artist = memorial.artist;
+        let seeds = &[b"memorial".as_ref(), artist.as_ref(), &[memorial.bump]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: memorial.to_account_info(),
+            },
+            signer,
+        ))?;
+        
+        memorial_state.total_memorials = memorial_state.total_memorials.saturating_sub(1);
+        
+        emit!(MemorialClosedEvent {
+            memorial: memorial.key(),
+            artist,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление медиа мемориала
+    pub fn update_memorial_media(
+        ctx: Context<UpdateMemorial>,
+        banner_cid: String,
+        last_mix_cid: String,
+        last_track_cid: String,
+    ) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        
+        require!(ctx.accounts.manager.key() == memorial.manager, ErrorCode::Unauthorized);
+        require!(memorial.status != MemorialStatus::Archived, ErrorCode::MemorialArchived);
+        require!(
+            banner_cid.len() <= MAX_CID_LEN
+                && last_mix_cid.len() <= MAX_CID_LEN
+                && last_track_cid.len() <= MAX_CID_LEN,
+            ErrorCode::NameTooLong
+        );
+        
+        memorial.banner_cid = banner_cid;
+        memorial.last_mix_cid = last_mix_cid;
+        memorial.last_track_cid = last_track_cid;
+        memorial.updated_at = Clock::get()?.unix_timestamp;
+        
+        Ok(())
+    }
+
+    // Добавление наследника. После верификации реестр меняет только администратор.
+    pub fn add_heir(ctx: Context<ManageHeirs>, wallet: Pubkey, share_bps: u16) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let authority = &ctx.accounts.authority;
+        
+        require_heir_registry_access(memorial, &ctx.accounts.memorial_state, &authority.key())?;
+        require!(memorial.heirs.len() < MAX_HEIRS, ErrorCode::TooManyHeirs);
+        require!(
+            !memorial.heirs.iter().any(|h| h.wallet == wallet),
+            ErrorCode::HeirAlreadyExists
+        );
+        
+        let allocated: u64 = memorial
+            .heirs
+            .iter()
+            .map(|h| h.share_bps as u64)
+            .sum();
+        require!(
+            share_bps > 0 && allocated + share_bps as u64 <= BPS_DENOMINATOR,
+            ErrorCode::InvalidHeirShare
+        );
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        memorial.heirs.push(Heir {
+            wallet,
+            share_bps,
+            added_at: current_time,
+        });
+        memorial.updated_at = current_time;
+        
+        emit!(HeirAddedEvent {
+            memorial: memorial.key(),
+            wallet,
+            share_bps,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Удаление наследника (освобождает место в реестре)
+    pub fn remove_heir(ctx: Context<ManageHeirs>, wallet: Pubkey) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let authority = &ctx.accounts.authority;
+        
+        require_heir_registry_access(memorial, &ctx.accounts.memorial_state, &authority.key())?;
+        
+        let position = memorial
+            .heirs
+            .iter()
+            .position(|h| h.wallet == wallet)
+            .ok_or(ErrorCode::HeirNotFound)?;
+        memorial.heirs.remove(position);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        memorial.updated_at = current_time;
+        
+        emit!(HeirRemovedEvent {
+            memorial: memorial.key(),
+            wallet,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Пожертвование в фонд мемориала (NDT)
+    pub fn donate(ctx: Context<Donate>, amount: u64, message: String) -> Result<()> {
+        let memorial_state = &mut ctx.accounts.memorial_state;
+        let memorial = &mut ctx.accounts.memorial;
+        let donor = &ctx.accounts.donor;
+        
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(message.len() <= MAX_TRIBUTE_LEN, ErrorCode::NameTooLong);
+        require!(memorial.status != MemorialStatus::Archived, ErrorCode::MemorialArchived);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.donor_token_account.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: donor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        memorial.total_donations = memorial.total_donations.checked_add(amount).unwrap();
+        memorial.donation_count = memorial.donation_count.checked_add(1).unwrap();
+        memorial.updated_at = current_time;
+        memorial_state.total_donations = memorial_state.total_donations.checked_add(amount).unwrap();
+        
+        emit!(DonationReceivedEvent {
+            memorial: memorial.key(),
+            donor: donor.key(),
+            amount,
+            message,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Трибьют: сообщение и, опционально, трек-посвящение
+    pub fn add_tribute(ctx: Context<AddTribute>, message: String, track_cid: String) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let tribute = &mut ctx.accounts.tribute;
+        let author = &ctx.accounts.author;
+        
+        require!(
+            !message.is_empty() && message.len() <= MAX_TRIBUTE_LEN,
+            ErrorCode::InvalidTribute
+        );
+        require!(track_cid.len() <= MAX_CID_LEN, ErrorCode::NameTooLong);
+        require!(memorial.status != MemorialStatus::Archived, ErrorCode::MemorialArchived);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        tribute.memorial = memorial.key();
+        tribute.author = author.key();
+        tribute.index = memorial.tribute_count;
+        tribute.message = message;
+        tribute.track_cid = track_cid;
+        tribute.created_at = current_time;
+        
+        memorial.tribute_count = memorial.tribute_count.checked_add(1).unwrap();
+        memorial.updated_at = current_time;
+        
+        emit!(TributeAddedEvent {
+            memorial: memorial.key(),
+            tribute: tribute.key(),
+            author: author.key(),
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Посещение мемориала
+    pub fn visit_memorial(ctx: Context<VisitMemorial>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        
+        memorial.visitors = memorial.visitors.checked_add(1).unwrap();
+        
+        Ok(())
+    }
+
+    // Верификация мемориала администратором (открывает выплаты наследникам)
+    pub fn verify_memorial(ctx: Context<SetMemorialStatus>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(memorial.status == MemorialStatus::Pending, ErrorCode::InvalidStatusChange);
+        
+        memorial.status = MemorialStatus::Verified;
+        memorial.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(MemorialStatusChangedEvent {
+            memorial: memorial.key(),
+            status: memorial.status,
+            timestamp: memorial.updated_at,
+        });
+        
+        Ok(())
+    }
+
+    // Архивация мемориала: пожертвования и трибьюты закрываются
+    pub fn archive_memorial(ctx: Context<SetMemorialStatus>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(memorial.status != MemorialStatus::Archived, ErrorCode::InvalidStatusChange);
+        
+        memorial.status = MemorialStatus::Archived;
+        memorial.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(MemorialStatusChangedEvent {
+            memorial: memorial.key(),
+            status: memorial.status,
+            timestamp: memorial.updated_at,
+        });
+        
+        Ok(())
+    }
+
+    // Распределение фонда (пожертвования и перенаправленная выручка треков).
+    // remaining_accounts: токен-аккаунты наследников в порядке реестра.
+    pub fn distribute_fund<'info>(ctx: Context<'_, '_, 'info, 'info, DistributeFund<'info>>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let authority = &ctx.accounts.authority;
+        
+        require!(
+            authority.key() == memorial.manager || authority.key() == ctx.accounts.memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(memorial.status == MemorialStatus::Verified, ErrorCode::MemorialNotVerified);
+        
+        let heirs = memorial.heirs.clone();
+        require!(!heirs.is_empty(), ErrorCode::NoHeirs);
+        require!(
+            ctx.remaining_accounts.len() == heirs.len(),
+            ErrorCode::InvalidHeirAccounts
+        );
+        
+        let fund = ctx.accounts.vault.amount;
+        require!(fund > 0, ErrorCode::NoFundsToDistribute);
+        
+        let artist = memorial.artist;
+        let seeds = &[b"memorial".as_ref(), artist.as_ref(), &[memorial.bump]];
+        let signer = &[&seeds[..]];
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        let mut distributed: u64 = 0;
+        for (heir, heir_info) in heirs.iter().zip(ctx.remaining_accounts.iter()) {
+            let heir_token_account: Account<'info, TokenAccount> = Account::try_from(heir_info)?;
+            require!(heir_token_account.owner == heir.wallet, ErrorCode::InvalidHeirAccounts);
+            require!(
+                heir_token_account.mint == ctx.accounts.memorial_state.ndt_mint,
+                ErrorCode::InvalidHeirAccounts
+            );
+            
+            let amount = (fund as u128)
+                .checked_mul(heir.share_bps as u128)
+                .unwrap()
+                .checked_div(BPS_DENOMINATOR as u128)
+                .unwrap() as u64;
+            if amount == 0 {
+                continue;
+            }
+            
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: heir_info.clone(),
+                        authority: memorial.to_account_info(),
+                    },
+                    signer,
+                ),
+                amount,
+            )?;
+            distributed = distributed.checked_add(amount).unwrap();
+            
+            emit!(FundDistributedEvent {
+                memorial: memorial.key(),
+                heir: heir.wallet,
+                amount,
+                timestamp: current_time,
+            });
+        }
+        
+        memorial.total_distributed = memorial.total_distributed.checked_add(distributed).unwrap();
+        memorial.updated_at = current_time;
+        
+        Ok(())
+    }
+
+    // Перевод в фонд выручки, пришедшей на другой токен-аккаунт PDA мемориала
+    // (tracknft проверяет только владельца аккаунта выплаты). Вызывает кто угодно.
+    pub fn sweep_to_vault(ctx: Context<SweepToVault>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let source = &ctx.accounts.source;
+        
+        let amount = source.amount;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        
+        let artist = memorial.artist;
+        let seeds = &[b"memorial".as_ref(), artist.as_ref(), &[memorial.bump]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: memorial.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        memorial.updated_at = Clock::get()?.unix_timestamp;
+        
+        emit!(FundsSweptEvent {
+            memorial: memorial.key(),
+            source: source.key(),
+            amount,
+            timestamp: memorial.updated_at,
+        });
+        
+        Ok(())
+    }
+
+    // Привязка трека артиста к мемориалу
+    pub fn add_catalog_track(ctx: Context<AddCatalogTrack>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let catalog_entry = &mut ctx.accounts.catalog_entry;
+        let track = &ctx.accounts.track;
+        
+        require!(
+            ctx.accounts.manager.key() == memorial.manager,
+            ErrorCode::Unauthorized
+        );
+        require!(memorial.status != MemorialStatus::Archived, ErrorCode::MemorialArchived);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        catalog_entry.memorial = memorial.key();
+        catalog_entry.track = track.key();
+        catalog_entry.play_count = track.play_count;
+        catalog_entry.like_count = track.like_count;
+        catalog_entry.sales_count = track.sales_count;
+        catalog_entry.payout_redirected = track.payout_wallet == memorial.key();
+        catalog_entry.added_at = current_time;
+        catalog_entry.synced_at = current_time;
+        
+        memorial.total_tracks = memorial.total_tracks.checked_add(1).unwrap();
+        memorial.total_plays = memorial.total_plays.checked_add(track.play_count).unwrap();
+        memorial.total_likes = memorial.total_likes.checked_add(track.like_count).unwrap();
+        memorial.total_sales = memorial.total_sales.checked_add(track.sales_count).unwrap();
+        memorial.updated_at = current_time;
+        
+        emit!(CatalogTrackAddedEvent {
+            memorial: memorial.key(),
+            track: track.key(),
+            payout_redirected: catalog_entry.payout_redirected,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Перенаправление выручки трека каталога в фонд мемориала.
+    // Только для верифицированного мемориала; tracknft проверяет подпись его PDA.
+    pub fn redirect_catalog_payout(ctx: Context<RedirectCatalogPayout>) -> Result<()> {
+        let memorial = &ctx.accounts.memorial;
+        let authority = &ctx.accounts.authority;
+        
+        require!(
+            authority.key() == memorial.manager || authority.key() == ctx.accounts.memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(memorial.status == MemorialStatus::Verified, ErrorCode::MemorialNotVerified);
+        
+        let artist = memorial.artist;
+        let seeds = &[b"memorial".as_ref(), artist.as_ref(), &[memorial.bump]];
+        let signer = &[&seeds[..]];
+        
+        tracknft::cpi::redirect_track_payout(CpiContext::new_with_signer(
+            ctx.accounts.tracknft_program.to_account_info(),
+            tracknft::cpi::accounts::RedirectTrackPayout {
+                track: ctx.accounts.track.to_account_info(),
+                memorial: memorial.to_account_info(),
+            },
+            signer,
+        ))?;
+        
+        let catalog_entry = &mut ctx.accounts.catalog_entry;
+        catalog_entry.payout_redirected = true;
+        catalog_entry.synced_at = Clock::get()?.unix_timestamp;
+        
+        emit!(PayoutRedirectedEvent {
+            memorial: memorial.key(),
+            target: ctx.accounts.track.key(),
+            timestamp: catalog_entry.synced_at,
+        });
+        
+        Ok(())
+    }
+
+    // Перенаправление поточных роялти и чаевых артиста в фонд мемориала
+    pub fn redirect_artist_royalties(ctx: Context<RedirectArtistRoyalties>) -> Result<()> {
+        let memorial = &ctx.accounts.memorial;
+        let authority = &ctx.accounts.authority;
+        
+        require!(
+            authority.key() == memorial.manager || authority.key() == ctx.accounts.memorial_state.authority,
+            ErrorCode::Unauthorized
+        );
+        require!(memorial.status == MemorialStatus::Verified, ErrorCode::MemorialNotVerified);
+        
+        let artist = memorial.artist;
+        let seeds = &[b"memorial".as_ref(), artist.as_ref(), &[memorial.bump]];
+        let signer = &[&seeds[..]];
+        
+        tracknft::cpi::redirect_creator_royalties(CpiContext::new_with_signer(
+            ctx.accounts.tracknft_program.to_account_info(),
+            tracknft::cpi::accounts::RedirectCreatorRoyalties {
+                royalty_account: ctx.accounts.royalty_account.to_account_info(),
+                memorial: memorial.to_account_info(),
+            },
+            signer,
+        ))?;
+        
+        emit!(PayoutRedirectedEvent {
+            memorial: memorial.key(),
+            target: ctx.accounts.royalty_account.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Синхронизация статистики трека (доступна любому)
+    pub fn sync_catalog_track(ctx: Context<SyncCatalogTrack>) -> Result<()> {
+        let memorial = &mut ctx.accounts.memorial;
+        let catalog_entry = &mut ctx.accounts.catalog_entry;
+        let track = &ctx.accounts.track;
+        
+        // Лайки могут уменьшаться (unlike_track), поэтому пересчитываем через разницу
+        memorial.total_plays = memorial
+            .total_plays
+            .saturating_sub(catalog_entry.play_count)
+            .checked_add(track.play_count)
+            .unwrap();
+        memorial.total_likes = memorial
+            .total_likes
+            .saturating_sub(catalog_entry.like_count)
+            .checked_add(track.like_count)
+            .unwrap();
+        memorial.total_sales = memorial
+            .total_sales
+            .saturating_sub(catalog_entry.sales_count)
+            .checked_add(track.sales_count)
+            .unwrap();
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        catalog_entry.play_count = track.play_count;
+        catalog_entry.like_count = track.like_count;
+        catalog_entry.sales_count = track.sales_count;
+        catalog_entry.payout_redirected = track.payout_wallet == memorial.key();
+        catalog_entry.synced_at = current_time;
+        memorial.updated_at = current_time;
+        
+        Ok(())
+    }
+}
+
+// До верификации реестром управляет менеджер, после — только администратор
+fn require_heir_registry_access(
+    memorial: &Memorial,
+    memorial_state: &MemorialState,
+    signer: &Pubkey,
+) -> Result<()> {
+    require!(memorial.status != MemorialStatus::Archived, ErrorCode::MemorialArchived);
+    let is_manager = *signer == memorial.manager && memorial.status == MemorialStatus::Pending;
+    require!(is_manager || *signer == memorial_state.authority, ErrorCode::Unauthorized);
+    Ok(())
+}
+
+// Структуры данных
+#[account]
+pub struct MemorialState {
+    pub authority: Pubkey,
+    pub ndt_mint: Pubkey,
+    pub total_memorials: u64,
+    pub total_donations: u64,
+}
+
+#[account]
+pub struct Memorial {
+    pub artist: Pubkey,  // Artist wallet, matches Track.creator
+    pub manager: Pubkey, // Memorial creator (family, label)
+    pub artist_name: String,
+    pub real_name: String,
+    pub birth_date: i64,
+    pub death_date: i64,
+    pub bio_cid: String,
+    pub avatar_cid: String,
+    pub banner_cid: String,
+    pub last_mix_cid: String,
+    pub last_track_cid: String,
+    pub memorial_type: MemorialType,
+    pub status: MemorialStatus,
+    pub heirs: Vec<Heir>,
+    pub total_tracks: u32,
+    pub total_plays: u64,      // Aggregated from catalog tracks
+    pub total_likes: u64,
+    pub total_sales: u64,
+    pub total_donations: u64,
+    pub donation_count: u64,
+    pub tribute_count: u64,
+    pub visitors: u64,
+    pub total_distributed: u64, // Paid out to heirs from the vault
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Heir {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+    pub added_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MemorialType {
+    Dj,
+    Producer,
+    Artist,
+    Collective,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MemorialStatus {
+    Pending,
+    Verified,
+    Archived,
+}
+
+#[account]
+pub struct Tribute {
+    pub memorial: Pubkey,
+    pub author: Pubkey,
+    pub index: u64,
+    pub message: String,
+    pub track_cid: String, // Tribute track on IPFS, may be empty
+    pub created_at: i64,
+}
+
+#[account]
+pub struct CatalogEntry {
+    pub memorial: Pubkey,
+    pub track: Pubkey,
+    pub play_count: u64,  // Track stats at the last sync
+    pub like_count: u64,
+    pub sales_count: u64,
+    pub payout_redirected: bool, // Track.payout_wallet is the memorial
+    pub added_at: i64,
+    pub synced_at: i64,
+}
+
+// События
+#[event]
+pub struct MemorialCreatedEvent {
+    pub memorial: Pubkey,
+    pub artist: Pubkey,
+    pub manager: Pubkey,
+    pub memorial_type: MemorialType,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ManagerReassignedEvent {
+    pub memorial: Pubkey,
+    pub previous_manager: Pubkey,
+    pub new_manager: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MemorialClosedEvent {
+    pub memorial: Pubkey,
+    pub artist: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HeirAddedEvent {
+    pub memorial: Pubkey,
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HeirRemovedEvent {
+    pub memorial: Pubkey,
+    pub wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DonationReceivedEvent {
+    pub memorial: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TributeAddedEvent {
+    pub memorial: Pubkey,
+    pub tribute: Pubkey,
+    pub author: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MemorialStatusChangedEvent {
+    pub memorial: Pubkey,
+    pub status: MemorialStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundDistributedEvent {
+    pub memorial: Pubkey,
+    pub heir: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsSweptEvent {
+    pub memorial: Pubkey,
+    pub source: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutRedirectedEvent {
+    pub memorial: Pubkey,
+    pub target: Pubkey, // Track or RoyaltyAccount
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CatalogTrackAddedEvent {
+    pub memorial: Pubkey,
+    pub track: Pubkey,
+    pub payout_redirected: bool,
+    pub timestamp: i64,
+}
+
+// Контексты
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 8,
+        seeds = [b"memorial_state"],
+        bump
+    )]
+    pub memorial_state: Account<'info, MemorialState>,
+    pub ndt_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(artist: Pubkey)]
+pub struct CreateMemorial<'info> {
+    #[account(mut, seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(
+        init,
+        payer = authority,
+        space = MEMORIAL_ACCOUNT_SPACE,
+        seeds = [b"memorial", artist.as_ref()],
+        bump
+    )]
+    pub memorial: Account<'info, Memorial>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"memorial_vault", memorial.key().as_ref()],
+        bump,
+        token::mint = ndt_mint,
+        token::authority = memorial
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(address = memorial_state.ndt_mint)]
+    pub ndt_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMemorial<'info> {
+    #[account(mut, seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"memorial", memorial.artist.as_ref()],
+        bump = memorial.bump
+    )]
+    pub memorial: Account<'info, Memorial>,
+    #[account(
+        mut,
+        seeds = [b"memorial_vault", memorial.key().as_ref()],
+        bump = memorial.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMemorial<'info> {
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    pub manager: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageHeirs<'info> {
+    #[account(seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Donate<'info> {
+    #[account(mut, seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(
+        mut,
+        seeds = [b"memorial_vault", memorial.key().as_ref()],
+        bump = memorial.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = donor_token_account.owner == donor.key() @ ErrorCode::InvalidDonorAccount,
+        constraint = donor_token_account.mint == memorial_state.ndt_mint @ ErrorCode::InvalidDonorAccount
+    )]
+    pub donor_token_account: Account<'info, TokenAccount>,
+    pub donor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddTribute<'info> {
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(
+        init,
+        payer = author,
+        space = 8 + 32 + 32 + 8 + (4 + MAX_TRIBUTE_LEN) + (4 + MAX_CID_LEN) + 8,
+        seeds = [b"tribute", memorial.key().as_ref(), &memorial.tribute_count.to_le_bytes()],
+        bump
+    )]
+    pub tribute: Account<'info, Tribute>,
+    #[account(mut)]
+    pub author: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VisitMemorial<'info> {
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    pub visitor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMemorialStatus<'info> {
+    #[account(seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeFund<'info> {
+    #[account(seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(
+        mut,
+        seeds = [b"memorial_vault", memorial.key().as_ref()],
+        bump = memorial.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SweepToVault<'info> {
+    #[account(seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(
+        mut,
+        seeds = [b"memorial_vault", memorial.key().as_ref()],
+        bump = memorial.vault_bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = source.owner == memorial.key() @ ErrorCode::InvalidSweepSource,
+        constraint = source.mint == memorial_state.ndt_mint @ ErrorCode::InvalidSweepSource,
+        constraint = source.key() != vault.key() @ ErrorCode::InvalidSweepSource
+    )]
+    pub source: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddCatalogTrack<'info> {
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(constraint = track.creator == memorial.artist @ ErrorCode::TrackNotByArtist)]
+    pub track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = manager,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 8,
+        seeds = [b"catalog", memorial.key().as_ref(), track.key().as_ref()],
+        bump
+    )]
+    pub catalog_entry: Account<'info, CatalogEntry>,
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedirectCatalogPayout<'info> {
+    #[account(seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(mut, address = catalog_entry.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        seeds = [b"catalog", memorial.key().as_ref(), track.key().as_ref()],
+        bump
+    )]
+    pub catalog_entry: Account<'info, CatalogEntry>,
+    pub authority: Signer<'info>,
+    pub tracknft_program: Program<'info, Tracknft>,
+}
+
+#[derive(Accounts)]
+pub struct RedirectArtistRoyalties<'info> {
+    #[account(seeds = [b"memorial_state"], bump)]
+    pub memorial_state: Account<'info, MemorialState>,
+    #[account(seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(mut, constraint = royalty_account.creator == memorial.artist @ ErrorCode::TrackNotByArtist)]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    pub authority: Signer<'info>,
+    pub tracknft_program: Program<'info, Tracknft>,
+}
+
+#[derive(Accounts)]
+pub struct SyncCatalogTrack<'info> {
+    #[account(mut, seeds = [b"memorial", memorial.artist.as_ref()], bump = memorial.bump)]
+    pub memorial: Account<'info, Memorial>,
+    #[account(address = catalog_entry.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        seeds = [b"catalog", memorial.key().as_ref(), track.key().as_ref()],
+        bump
+    )]
+    pub catalog_entry: Account<'info, CatalogEntry>,
+}
+
+// Коды ошибок
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    #[msg("Artist name and a valid death date are required")]
+    InvalidMemorialData,
+    #[msg("Text field is too long")]
+    NameTooLong,
+    #[msg("Heir registry is full")]
+    TooManyHeirs,
+    #[msg("Heir shares must be positive and sum to at most 100%")]
+    InvalidHeirShare,
+    #[msg("Heir is already registered")]
+    HeirAlreadyExists,
+    #[msg("Heir not found")]
+    HeirNotFound,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Memorial is archived")]
+    MemorialArchived,
+    #[msg("Memorial is not verified")]
+    MemorialNotVerified,
+    #[msg("Invalid memorial status change")]
+    InvalidStatusChange,
+    #[msg("No heirs defined")]
+    NoHeirs,
+    #[msg("No funds to distribute")]
+    NoFundsToDistribute,
+    #[msg("Heir token accounts do not match the heir registry")]
+    InvalidHeirAccounts,
+    #[msg("Donor token account is not owned by the donor or has the wrong mint")]
+    InvalidDonorAccount,
+    #[msg("Tribute message is empty or too long")]
+    InvalidTribute,
+    #[msg("Track was not created by the memorial artist")]
+    TrackNotByArtist,
+    #[msg("Memorial fund must be distributed first")]
+    FundNotEmpty,
+    #[msg("Source must be a memorial-owned NDT account other than the vault")]
+    InvalidSweepSource,
+}
@@ -1,8 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
-use std::str::FromStr;
 
-declare_id!("NDT111111111111111111111111111111111111111111");
+declare_id!("skzJ4ZxZmcx9pSTPHkmVrgBmZH4x4bnbpRnkTs5e8yR");
 
 #[program]
 pub mod ndt {
@@ -17,6 +16,32 @@ pub mod ndt {
         ndt.total_supply = 0;
         ndt.burn_percentage = 1; // 1% сжигание при транзакциях
         ndt.staking_apr = 5; // 5% базовый APY для стейкинга
+        ndt.reward_pool = Pubkey::default();
+        ndt.reward_share = 0;
+        
+        Ok(())
+    }
+
+    // Направление части сжигаемой комиссии в пул наград чарта
+    pub fn set_reward_pool(ctx: Context<SetRewardPool>, reward_pool: Pubkey, reward_share: u64) -> Result<()> {
+        let ndt = &mut ctx.accounts.ndt;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == ndt.authority, ErrorCode::Unauthorized);
+        require!(reward_share <= 100, ErrorCode::InvalidRewardShare);
+        require!(
+            reward_share == 0 || reward_pool != Pubkey::default(),
+            ErrorCode::InvalidRewardShare
+        );
+        
+        ndt.reward_pool = reward_pool;
+        ndt.reward_share = reward_share;
+        
+        emit!(RewardPoolUpdatedEvent {
+            reward_pool,
+            reward_share,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
         Ok(())
     }
@@ -44,8 +69,8 @@ pub mod ndt {
                     to: ctx.accounts.destination.to_account_info(),
                     authority: ndt.to_account_info(),
                 },
+                signer,
             ),
-            signer,
             amount,
         )?;
         
@@ -63,6 +88,10 @@ pub mod ndt {
         let burn_amount = amount.checked_mul(ndt.burn_percentage).unwrap() / 100;
         let transfer_amount = amount.checked_sub(burn_amount).unwrap();
         
+        // Часть комиссии вместо сжигания уходит в пул наград
+        let reward_amount = burn_amount.checked_mul(ndt.reward_share).unwrap() / 100;
+        let burn_amount = burn_amount.checked_sub(reward_amount).unwrap();
+        
         // Переводим токены
         anchor_spl::token::transfer(
             CpiContext::new(
@@ -76,6 +105,26 @@ pub mod ndt {
             transfer_amount,
         )?;
         
+        if reward_amount > 0 {
+            let reward_pool = ctx
+                .accounts
+                .reward_pool
+                .as_ref()
+                .ok_or(ErrorCode::MissingRewardPool)?;
+            
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: from.to_account_info(),
+                        to: reward_pool.to_account_info(),
+                        authority: authority.to_account_info(),
+                    },
+                ),
+                reward_amount,
+            )?;
+        }
+        
         // Сжигаем токены
         anchor_spl::token::burn(
             CpiContext::new(
@@ -97,6 +146,7 @@ pub mod ndt {
             to: to.key(),
             amount: transfer_amount,
             burn_amount,
+            reward_amount,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -150,7 +200,6 @@ pub mod ndt {
 
     // Unstaking токенов
     pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
-        let ndt = &mut ctx.accounts.ndt;
         let staker = &ctx.accounts.staker;
         let staking_account = &mut ctx.accounts.staking_account;
         let authority = &ctx.accounts.authority;
@@ -160,7 +209,7 @@ pub mod ndt {
         // Проверяем, истек ли период блокировки
         let current_time = Clock::get()?.unix_timestamp;
         require!(
-            current_time >= staking_account.stake_time + staking_account.lock_period,
+            current_time >= staking_account.stake_time + staking_account.lock_period as i64,
             ErrorCode::LockPeriodNotExpired
         );
         
@@ -195,16 +244,15 @@ pub mod ndt {
         let staker = &ctx.accounts.staker;
         let staking_account = &mut ctx.accounts.staking_account;
         let rewards_account = &mut ctx.accounts.rewards_account;
-        let authority = &ctx.accounts.authority;
         
         let current_time = Clock::get()?.unix_timestamp;
         let time_elapsed = current_time.checked_sub(staking_account.last_claim_time).unwrap();
         
         // Рассчитываем накопленные rewards
         let rewards = staking_account.amount
-            .checked_mul(staking_account.apr as u64)
+            .checked_mul(staking_account.apr)
             .unwrap()
-            .checked_mul(time_elapsed)
+            .checked_mul(time_elapsed as u64)
             .unwrap()
             .checked_div(365 * 24 * 60 * 60) // Год в секундах
             .unwrap()
@@ -225,8 +273,8 @@ pub mod ndt {
                     to: rewards_account.to_account_info(),
                     authority: ndt.to_account_info(),
                 },
+                signer,
             ),
-            signer,
             rewards,
         )?;
         
@@ -275,6 +323,8 @@ pub struct NdtState {
     pub total_supply: u64,
     pub burn_percentage: u64, // Percentage of tokens burned per transaction
     pub staking_apr: u64,    // Base APR for staking
+    pub reward_pool: Pubkey, // Chart reward vault receiving part of the transfer fee
+    pub reward_share: u64,   // Percentage of the burn amount sent to reward_pool
 }
 
 #[account]
@@ -295,6 +345,14 @@ pub struct TransferEvent {
     pub to: Pubkey,
     pub amount: u64,
     pub burn_amount: u64,
+    pub reward_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardPoolUpdatedEvent {
+    pub reward_pool: Pubkey,
+    pub reward_share: u64,
     pub timestamp: i64,
 }
 
@@ -324,7 +382,13 @@ pub struct ClaimRewardsEvent {
 // Contexts
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(init, payer = authority, seeds = [b"ndt"], bump)]
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 8 + 32 + 8,
+        seeds = [b"ndt"],
+        bump
+    )]
     pub ndt: Account<'info, NdtState>,
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -353,10 +417,24 @@ pub struct Transfer<'info> {
     pub from: Account<'info, TokenAccount>,
     #[account(mut)]
     pub to: Account<'info, TokenAccount>,
+    // Required only while part of the fee goes to the reward pool
+    #[account(
+        mut,
+        address = ndt.reward_pool,
+        constraint = reward_pool.mint == from.mint @ ErrorCode::InvalidRewardPool
+    )]
+    pub reward_pool: Option<Account<'info, TokenAccount>>,
     pub authority: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct SetRewardPool<'info> {
+    #[account(mut, seeds = [b"ndt"], bump)]
+    pub ndt: Account<'info, NdtState>,
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct Stake<'info> {
     #[account(mut, seeds = [b"ndt"], bump)]
@@ -375,6 +453,7 @@ pub struct Stake<'info> {
     pub staking_account: Account<'info, StakingAccount>,
     #[account(mut)]
     pub rewards_account: Account<'info, TokenAccount>,
+    #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
@@ -399,6 +478,8 @@ pub struct ClaimRewards<'info> {
     #[account(mut, seeds = [b"ndt"], bump)]
     pub ndt: Account<'info, NdtState>,
     #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
     pub staker: Account<'info, TokenAccount>,
     #[account(mut, seeds = [b"staking", staker.key().as_ref()], bump)]
     pub staking_account: Account<'info, StakingAccount>,
@@ -419,4 +500,10 @@ pub enum ErrorCode {
     LockPeriodNotExpired,
     #[msg("No rewards to claim")]
     NoRewardsToClaim,
+    #[msg("Invalid reward pool share")]
+    InvalidRewardShare,
+    #[msg("Reward pool account is required")]
+    MissingRewardPool,
+    #[msg("Reward pool holds a different mint")]
+    InvalidRewardPool,
 }
\ No newline at end of file
@@ -1,8 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
-use std::str::FromStr;
 
-declare_id!("STAKING111111111111111111111111111111111111111");
+declare_id!("GgjkSA6fg5RvyygF6sBKYoy5Xx1R8TRFHUXu9JwLhC1q");
 
 #[program]
 pub mod staking {
@@ -114,9 +113,9 @@ pub mod staking {
         
         // Рассчитываем накопленные rewards
         let rewards = staker.amount
-            .checked_mul(staker.apy as u64)
+            .checked_mul(staker.apy)
             .unwrap()
-            .checked_mul(time_elapsed)
+            .checked_mul(time_elapsed as u64)
             .unwrap()
             .checked_div(365 * 24 * 60 * 60) // Год в секундах
             .unwrap()
@@ -137,8 +136,8 @@ pub mod staking {
                     to: rewards_account.to_account_info(),
                     authority: staking_pool.to_account_info(),
                 },
+                signer,
             ),
-            signer,
             rewards,
         )?;
         
@@ -236,9 +235,9 @@ pub mod staking {
         
         let time_elapsed = current_time.checked_sub(staker.last_claim_time).unwrap();
         let pending_rewards = staker.amount
-            .checked_mul(staker.apy as u64)
+            .checked_mul(staker.apy)
             .unwrap()
-            .checked_mul(time_elapsed)
+            .checked_mul(time_elapsed as u64)
             .unwrap()
             .checked_div(365 * 24 * 60 * 60)
             .unwrap()
@@ -304,8 +303,6 @@ fn calculate_staking_level(total_staked: u64, staking_pool: &Account<StakingPool
         StakingTier::Gold
     } else if total_staked >= staking_pool.silver_threshold {
         StakingTier::Silver
-    } else if total_staked >= staking_pool.bronze_threshold {
-        StakingTier::Bronze
     } else {
         StakingTier::Bronze
     }
@@ -408,7 +405,13 @@ pub struct TierThresholdsUpdatedEvent {
 // Contexts
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(init, payer = authority, seeds = [b"staking"], bump)]
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1,
+        seeds = [b"staking"],
+        bump
+    )]
     pub staking_pool: Account<'info, StakingPool>,
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -429,6 +432,7 @@ pub struct Stake<'info> {
     pub staker: Account<'info, Staker>,
     #[account(mut)]
     pub rewards_account: Account<'info, TokenAccount>,
+    #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
@@ -450,6 +454,8 @@ pub struct Unstake<'info> {
 pub struct ClaimRewards<'info> {
     #[account(mut, seeds = [b"staking"], bump)]
     pub staking_pool: Account<'info, StakingPool>,
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
     #[account(mut, seeds = [b"staker", authority.key().as_ref()], bump)]
     pub staker: Account<'info, Staker>,
     #[account(mut)]
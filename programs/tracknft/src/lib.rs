@@ -1,8 +1,100 @@
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as ix_sysvar};
 use anchor_spl::token::{Mint, Token, TokenAccount};
+use mpl_bubblegum::instructions::{BurnCpiBuilder, CreateTreeConfigCpiBuilder, MintV1CpiBuilder};
+use mpl_bubblegum::types::{Creator, MetadataArgs, TokenProgramVersion, TokenStandard};
 use std::str::FromStr;
 
-declare_id!("TRACKNFT111111111111111111111111111111111111111");
+declare_id!("9NHwYoSJbtYVWAGFFi9sq8Aa5RjZnZAEV8W9p6DTkmnm");
+
+// Комиссия платформы в базисных пунктах (1 bps = 0.01%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const DEFAULT_PLATFORM_FEE_BPS: u16 = 500; // 5% по умолчанию
+pub const MAX_PLATFORM_FEE_BPS: u16 = 1_000; // Не более 10%
+pub const DEFAULT_TIP_FEE_BPS: u16 = 100; // Сниженная комиссия на чаевые: 1%
+
+// Сплит-лист создателя (доли соавторов в базисных пунктах)
+pub const MAX_SPLIT_RECIPIENTS: usize = 8;
+
+// Ограничения частоты воспроизведений для одного слушателя
+pub const DEFAULT_MIN_PLAY_INTERVAL: i64 = 30; // 30 секунд между прослушиваниями
+pub const DEFAULT_MAX_DAILY_PLAYS: u32 = 500;
+pub const PLAY_WINDOW_SECONDS: i64 = 24 * 60 * 60;
+
+// Размер аккаунта Track и версия его раскладки
+pub const TRACK_ACCOUNT_SPACE: usize = 8 + 32 + 32 + 32 + 200 + 200 + 8 + 1 + 8 + 8 + 8 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 1 + 1 + 32
+    + 4 + MAX_REMIX_PARENTS * (32 + 2) + 8 + 8 + 1 + 1 + 8 + 1 + 1 + 8 + 8 + 8 + 32 + 1 + 32 + 32;
+pub const TRACK_LAYOUT_VERSION: u8 = 17;
+
+// Лицензия на трек (PDA на пару track + owner)
+pub const LICENSE_ACCOUNT_SPACE: usize = 8 + 32 + 32 + 8 + 8 + 4 + 8 + 4;
+
+// Максимальная длина сообщения к подарку
+pub const MAX_GIFT_MESSAGE_LENGTH: usize = 200;
+
+// Сжатые экземпляры (cNFT) Bubblegum в деревьях артиста
+pub const MAX_EDITION_TREE_DEPTH: u32 = 30;
+pub const MAX_EDITION_NAME_LEN: usize = 32;
+pub const EDITION_SYMBOL: &str = "TRACK";
+
+// Споры об авторских правах: залог заявителя по умолчанию
+pub const DEFAULT_CLAIM_BOND: u64 = 100_000_000_000; // 100 токенов при 9 знаках
+
+// Окно возврата покупки
+pub const MAX_REFUND_WINDOW: i64 = 14 * 24 * 60 * 60;
+
+// Кривая цены: рост экспоненциальной кривой за экземпляр и точность расчета
+pub const MAX_CURVE_GROWTH_BPS: u64 = 1_000; // Не более 10% за экземпляр
+pub const CURVE_SCALE: u128 = 1_000_000_000;
+
+// Аренда трека: доступ на ограниченное время
+pub const MAX_RENTAL_DURATION: i64 = 30 * 24 * 60 * 60;
+
+// Ремиксы: доли родительских треков в продажах производного
+pub const MAX_REMIX_PARENTS: usize = 4;
+pub const MAX_UPSTREAM_SHARE_BPS: u16 = 5_000; // Не более 50% дохода артиста
+
+// Франшизы: доли дохода трека
+pub const MAX_FRANCHISE_SHARE_BPS: u16 = 5_000; // Не более 50% дохода артиста
+pub const SHARE_PRECISION: u128 = 1_000_000_000_000;
+
+// Альбомы
+pub const MAX_ALBUM_TRACKS: usize = 20;
+pub const MAX_ALBUM_TITLE_LEN: usize = 64;
+
+// Профиль артиста
+pub const MAX_DISPLAY_NAME_LEN: usize = 64;
+pub const MAX_AVATAR_CID_LEN: usize = 64;
+pub const ARTIST_PROFILE_SPACE: usize = 8 + 32 + (4 + MAX_DISPLAY_NAME_LEN) + (4 + MAX_AVATAR_CID_LEN) + 32 + 1 + 32 + 1
+    + (4 + MAX_SPLIT_RECIPIENTS * (32 + 2)) + 4 + 8 + 8 + 1;
+
+// Пакетное начисление воспроизведений от агрегатора
+pub const PLAY_EPOCH_SECONDS: i64 = 60 * 60; // Эпоха = 1 час
+pub const MAX_SETTLEMENT_BATCH: usize = 20;
+
+// Недельный чарт: размер таблицы лидеров и вес продажи относительно прослушивания
+pub const CHART_SIZE: usize = 50;
+pub const CHART_WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+pub const CHART_SETTLEMENT_GRACE: i64 = 6 * 60 * 60; // Запас на досчет последних эпох недели
+pub const CHART_SALE_WEIGHT: u64 = 10;
+pub const CHART_MIN_SALE_PRICE: u64 = 1_000_000_000; // 1 токен при 9 знаках, дешевле продажа не учитывается
+
+// Недельные награды чарта в NDT: срок получения после открытия пула
+pub const CHART_CLAIM_WINDOW: i64 = 4 * CHART_WEEK_SECONDS;
+
+// Подписки: пул периода делится между создателями пропорционально прослушиваниям
+pub const SUBSCRIPTION_PERIOD_SECONDS: i64 = 30 * 24 * 60 * 60;
+// Оплата плана делится поровну между периодами его срока; доли будущих периодов
+// ждут в кольцевом графике, поэтому срок плана и предоплата ограничены его размером
+pub const SUBSCRIPTION_SCHEDULE_PERIODS: usize = 25;
+pub const MAX_PLAN_PERIODS: i64 = 12;
+pub const PERIOD_PLAYS_SPACE: usize = 8 + 8 + 32 + 8;
+
+// Аттестация отпечатка: префикс не дает переиспользовать подпись оракула в другом протоколе
+pub const CONTENT_ATTESTATION_DOMAIN: &[u8] = b"normaldance:content:v1";
 
 #[program]
 pub mod tracknft {
@@ -16,29 +108,238 @@ pub mod tracknft {
         track_nft.authority = authority.key();
         track_nft.royalty_percentage = 10; // 10% роялти по умолчанию
         track_nft.total_tracks = 0;
+        track_nft.platform_fee_bps = DEFAULT_PLATFORM_FEE_BPS;
+        track_nft.payment_mint = ctx.accounts.payment_mint.key();
+        track_nft.treasury = ctx.accounts.treasury.key();
+        track_nft.treasury_bump = ctx.bumps.treasury;
+        track_nft.total_fees_collected = 0;
+        track_nft.play_oracle = Pubkey::default();
+        track_nft.min_play_interval = DEFAULT_MIN_PLAY_INTERVAL;
+        track_nft.max_daily_plays = DEFAULT_MAX_DAILY_PLAYS;
+        track_nft.pending_stream_fees = 0;
+        track_nft.tip_fee_bps = DEFAULT_TIP_FEE_BPS;
+        track_nft.content_oracle = Pubkey::default();
+        track_nft.arbiter = authority.key();
+        track_nft.claim_bond = DEFAULT_CLAIM_BOND;
+        track_nft.refund_window = 0;
+        
+        Ok(())
+    }
+
+    // Создание хранилища предоплаченных балансов слушателей
+    pub fn initialize_stream_vault(ctx: Context<InitializeStreamVault>) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        
+        track_nft.stream_vault_bump = ctx.bumps.stream_vault;
+        
+        Ok(())
+    }
+
+    // Создание эскроу выручки на время окна возврата
+    pub fn initialize_sale_escrow(ctx: Context<InitializeSaleEscrow>) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        
+        track_nft.sale_escrow_bump = ctx.bumps.sale_escrow;
+        
+        Ok(())
+    }
+
+    // Длительность окна возврата (0 - выплата сразу при покупке).
+    // Окно действует для buy_track и gift_track; аренда и альбомы
+    // оплачиваются сразу и возврату не подлежат.
+    pub fn set_refund_window(ctx: Context<SetPlayOracle>, refund_window: i64) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(
+            (0..=MAX_REFUND_WINDOW).contains(&refund_window),
+            ErrorCode::InvalidRefundWindow
+        );
+        require!(
+            refund_window == 0 || track_nft.sale_escrow_bump != 0,
+            ErrorCode::MissingRefundEscrow
+        );
+        
+        track_nft.refund_window = refund_window;
+        
+        Ok(())
+    }
+
+    // Настройка оракула воспроизведений и лимитов
+    pub fn set_play_oracle(
+        ctx: Context<SetPlayOracle>,
+        play_oracle: Pubkey,
+        min_play_interval: i64,
+        max_daily_plays: u32,
+    ) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(min_play_interval >= 0 && max_daily_plays > 0, ErrorCode::InvalidPlayLimits);
+        
+        track_nft.play_oracle = play_oracle;
+        track_nft.min_play_interval = min_play_interval;
+        track_nft.max_daily_plays = max_daily_plays;
+        
+        emit!(PlayOracleUpdatedEvent {
+            play_oracle,
+            min_play_interval,
+            max_daily_plays,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Изменение комиссии платформы
+    pub fn set_platform_fee(ctx: Context<SetPlatformFee>, new_fee_bps: u16) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(new_fee_bps <= MAX_PLATFORM_FEE_BPS, ErrorCode::PlatformFeeTooHigh);
+        require!(new_fee_bps >= track_nft.tip_fee_bps, ErrorCode::PlatformFeeBelowTipFee);
+        
+        let old_fee_bps = track_nft.platform_fee_bps;
+        track_nft.platform_fee_bps = new_fee_bps;
+        
+        emit!(PlatformFeeUpdatedEvent {
+            old_fee_bps,
+            new_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Изменение комиссии на чаевые (не выше основной комиссии)
+    pub fn set_tip_fee(ctx: Context<SetPlatformFee>, new_fee_bps: u16) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(new_fee_bps <= track_nft.platform_fee_bps, ErrorCode::PlatformFeeTooHigh);
+        
+        let old_fee_bps = track_nft.tip_fee_bps;
+        track_nft.tip_fee_bps = new_fee_bps;
+        
+        emit!(TipFeeUpdatedEvent {
+            old_fee_bps,
+            new_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Вывод средств из казны протокола
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
+        let treasury = &ctx.accounts.treasury;
+        let destination = &ctx.accounts.destination;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(amount > 0 && amount <= treasury.amount, ErrorCode::InsufficientTreasuryFunds);
+        
+        // Казна принадлежит PDA программы
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: treasury.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        emit!(TreasuryWithdrawnEvent {
+            destination: destination.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
         Ok(())
     }
 
-    // Создание музыкального NFT
-    pub fn create_track(
-        ctx: Context<CreateTrack>,
+    // Ключ антипиратского API, подписывающего отпечатки аудио
+    pub fn set_content_oracle(ctx: Context<SetPlayOracle>, content_oracle: Pubkey) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        
+        track_nft.content_oracle = content_oracle;
+        
+        emit!(ContentOracleUpdatedEvent {
+            content_oracle,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Создание музыкального NFT от имени профиля артиста.
+    // Предыдущая инструкция - ed25519-подпись content_oracle над
+    // (fingerprint, authority, ipfs_hash, родительские треки); отпечаток
+    // закрепляется за треком. Оракул перечисляет найденные в аудио сэмплы,
+    // поэтому родословную нельзя опустить.
+    // remaining_accounts: пары (родительский track, remix license) для ремиксов.
+    pub fn create_track<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateTrack<'info>>,
         track_name: String,
-        artist_name: String,
         ipfs_hash: String,
         price: u64,
-        royalty_percentage: u8,
+        royalty_percentage: Option<u8>,
+        fingerprint: [u8; 32],
     ) -> Result<()> {
         let track_nft = &mut ctx.accounts.track_nft;
         let track = &mut ctx.accounts.track;
-        let artist = &ctx.accounts.artist;
+        let content_record = &mut ctx.accounts.content_record;
         let authority = &ctx.accounts.authority;
         
+        // Профиль артиста задает имя, роялти по умолчанию и кошелек выплат.
+        // Имя верифицированного артиста доступно только его профилю.
+        let artist_profile = &mut ctx.accounts.artist_profile;
+        require!(
+            artist_profile.status != VerificationStatus::Suspended,
+            ErrorCode::ArtistProfileSuspended
+        );
+        check_artist_name_claim(&ctx.accounts.name_claim, &authority.key())?;
+        artist_profile.track_count = artist_profile.track_count.checked_add(1).unwrap();
+        let royalty_percentage = royalty_percentage.unwrap_or(artist_profile.default_royalty);
+        
         require!(royalty_percentage <= 50, ErrorCode::RoyaltyTooHigh);
+        require!(track_nft.content_oracle != Pubkey::default(), ErrorCode::ContentOracleNotSet);
+        
+        // Один отпечаток - один трек
+        // Ошибка несет адрес уже зарегистрированного трека
+        require_keys_eq!(content_record.track, Pubkey::default(), ErrorCode::DuplicateContent);
+        let parents: Vec<Pubkey> = ctx.remaining_accounts.chunks(2).map(|pair| pair[0].key()).collect();
+        let message = content_attestation_message(&fingerprint, &authority.key(), &ipfs_hash, &parents);
+        verify_ed25519_attestation(
+            &ctx.accounts.instructions.to_account_info(),
+            &track_nft.content_oracle,
+            &message,
+        )?;
         
         // Инициализируем NFT
         track.track_name = track_name;
-        track.artist_name = artist_name;
+        track.artist_name = artist_profile.display_name.clone();
         track.ipfs_hash = ipfs_hash;
         track.price = price;
         track.royalty_percentage = royalty_percentage;
@@ -46,379 +347,7154 @@ pub mod tracknft {
         track.play_count = 0;
         track.like_count = 0;
         track.total_royalties_paid = 0;
-        track.creator = artist.key();
+        track.creator = authority.key();
         track.mint_time = Clock::get()?.unix_timestamp;
+        track.sales_count = 0;
+        track.gross_revenue = 0;
+        track.unique_buyers = 0;
+        track.layout_version = TRACK_LAYOUT_VERSION;
+        track.stream_price = 0;
+        track.stats_period = 0;
+        track.period_plays = 0;
+        track.prev_period_plays = 0;
+        track.has_drop = false;
+        track.has_franchise = false;
+        track.payout_wallet = artist_profile.payout_wallet;
+        track.upstream = Vec::new();
+        track.rental_price = 0;
+        track.rental_duration = 0;
+        track.compressed_editions = false;
+        track.edition_tree = Pubkey::default();
+        track.under_dispute = false;
+        track.refund_count = 0;
+        track.transferable = false;
+        track.has_curve = false;
+        track.chart_week = 0;
+        track.week_plays = 0;
+        track.week_sales = 0;
+        track.artist_profile = artist_profile.key();
+        track.taken_down = false;
+        track.fingerprint = fingerprint;
+        
+        content_record.fingerprint = fingerprint;
+        content_record.track = track.key();
+        content_record.creator = authority.key();
+        content_record.registered_at = track.mint_time;
+        
+        // Родословная ремикса: каждая доля одобрена лицензией родителя
+        require!(ctx.remaining_accounts.len().is_multiple_of(2), ErrorCode::InvalidRemixAccounts);
+        require!(
+            ctx.remaining_accounts.len() / 2 <= MAX_REMIX_PARENTS,
+            ErrorCode::TooManyRemixParents
+        );
+        let mut upstream_bps: u64 = 0;
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let parent: Account<'info, Track> = Account::try_from(&pair[0])?;
+            let mut remix_license: Account<'info, RemixLicense> = Account::try_from(&pair[1])?;
+            
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"remix_license", parent.key().as_ref(), authority.key().as_ref()],
+                &crate::ID,
+            );
+            require!(remix_license.key() == expected, ErrorCode::InvalidRemixLicense);
+            require!(
+                remix_license.max_derivatives == 0 || remix_license.derivatives < remix_license.max_derivatives,
+                ErrorCode::RemixLicenseExhausted
+            );
+            require!(
+                !track.upstream.iter().any(|u| u.parent_track == parent.key()),
+                ErrorCode::InvalidRemixAccounts
+            );
+            
+            upstream_bps = upstream_bps.checked_add(remix_license.share_bps as u64).unwrap();
+            track.upstream.push(UpstreamShare {
+                parent_track: parent.key(),
+                share_bps: remix_license.share_bps,
+            });
+            
+            remix_license.derivatives = remix_license.derivatives.checked_add(1).unwrap();
+            remix_license.exit(&crate::ID)?;
+            
+            emit!(RemixRegisteredEvent {
+                track: track.key(),
+                parent_track: parent.key(),
+                parent_creator: parent.creator,
+                share_bps: remix_license.share_bps,
+                timestamp: track.mint_time,
+            });
+        }
+        require!(upstream_bps <= MAX_UPSTREAM_SHARE_BPS as u64, ErrorCode::UpstreamShareTooHigh);
         
         // Увеличиваем счетчик треков
         track_nft.total_tracks = track_nft.total_tracks.checked_add(1).unwrap();
         
         emit!(TrackCreatedEvent {
             track: track.key(),
-            creator: artist.key(),
+            creator: authority.key(),
             track_name: track.track_name.clone(),
             price,
             royalty_percentage,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
+        emit!(ContentRegisteredEvent {
+            fingerprint,
+            track: track.key(),
+            creator: authority.key(),
+            timestamp: content_record.registered_at,
+        });
+        
         Ok(())
     }
 
     // Покупка трека
-    pub fn buy_track(ctx: Context<BuyTrack>, track_id: Pubkey) -> Result<()> {
+    // remaining_accounts: пары (родительский track, токен-аккаунт его payout_wallet)
+    // в порядке track.upstream.
+    pub fn buy_track<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyTrack<'info>>,
+        track_id: Pubkey,
+        allowlist_proof: Vec<[u8; 32]>,
+        coupon_code: Option<String>,
+    ) -> Result<()> {
         let track_nft = &mut ctx.accounts.track_nft;
         let track = &mut ctx.accounts.track;
         let buyer = &ctx.accounts.buyer;
-        let artist = &ctx.accounts.artist;
+        let buyer_token_account = &ctx.accounts.buyer_token_account;
+        let artist_token_account = &ctx.accounts.artist_token_account;
         let creator_royalties = &mut ctx.accounts.creator_royalties;
-        let platform_fees = &mut ctx.accounts.platform_fees;
-        let authority = &ctx.accounts.authority;
+        let treasury = &mut ctx.accounts.treasury;
+        let license = &mut ctx.accounts.license;
         
+        require_keys_eq!(track.key(), track_id, ErrorCode::InvalidTrackAccount);
         require!(track.is_listed, ErrorCode::TrackNotListed);
+        require!(!track.taken_down, ErrorCode::TrackTakenDown);
         require!(buyer.key() != track.creator, ErrorCode::CreatorCannotBuy);
         
-        let track_price = track.price;
-        let royalty_amount = track_price
-            .checked_mul(track.royalty_percentage as u64)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
-        
-        let platform_fee = track_price
-            .checked_mul(5) // 5% платформе
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
-        
-        let artist_amount = track_price
-            .checked_sub(royalty_amount)
-            .unwrap()
-            .checked_sub(platform_fee)
-            .unwrap();
+        let current_time = Clock::get()?.unix_timestamp;
         
-        // Переводим средства артисту
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: buyer.to_account_info(),
-                    to: artist.to_account_info(),
-                    authority: authority.to_account_info(),
-                },
-            ),
-            artist_amount,
-        )?;
+        // Фазы дропа: allowlist по Merkle-корню, затем публичная продажа
+        let mut track_price = track.price;
+        let mut reserve_cut = 0;
+        if track.has_drop {
+            let drop_config = ctx
+                .accounts
+                .drop_config
+                .as_mut()
+                .ok_or(ErrorCode::MissingDropConfig)?;
+            match drop_config.phase_at(current_time) {
+                DropPhase::Allowlist => {
+                    let leaf = hashv(&[buyer.key().as_ref()]).to_bytes();
+                    require!(
+                        verify_merkle_proof(&allowlist_proof, drop_config.allowlist_root, leaf),
+                        ErrorCode::NotOnAllowlist
+                    );
+                    // Лимит считается по покупкам кошелька, а не по лицензии:
+                    // переданные экземпляры не освобождают место в пресейле
+                    let drop_buyer = ctx
+                        .accounts
+                        .drop_buyer
+                        .as_mut()
+                        .ok_or(ErrorCode::MissingDropConfig)?;
+                    require!(
+                        drop_buyer.purchased < drop_config.max_per_wallet,
+                        ErrorCode::WalletCapReached
+                    );
+                    require!(
+                        drop_config.presale_supply == 0 || drop_config.presale_sold < drop_config.presale_supply,
+                        ErrorCode::PresaleSoldOut
+                    );
+                    drop_buyer.track = track.key();
+                    drop_buyer.buyer = buyer.key();
+                    drop_buyer.purchased = drop_buyer.purchased.checked_add(1).unwrap();
+                    drop_buyer.bump = ctx.bumps.drop_buyer.unwrap();
+                    track_price = drop_config.presale_price;
+                    drop_config.presale_sold = drop_config.presale_sold.checked_add(1).unwrap();
+                }
+                DropPhase::Public => {}
+                DropPhase::Closed => return err!(ErrorCode::DropPhaseClosed),
+            }
+        }
         
-        // Выплачиваем роялти создателю (если это не артист)
-        if artist.key() != track.creator {
+        // Цена по кривой, часть ее уходит в резерв выкупа
+        if track.has_curve {
+            let bonding_curve = ctx
+                .accounts
+                .bonding_curve
+                .as_ref()
+                .ok_or(ErrorCode::MissingBondingCurve)?;
+            let curve_reserve = ctx
+                .accounts
+                .curve_reserve
+                .as_ref()
+                .ok_or(ErrorCode::MissingBondingCurve)?;
+            
+            track_price = bonding_curve.price_at(bonding_curve.supply);
+            reserve_cut = calculate_bps(track_price, bonding_curve.reserve_bps);
+            
             anchor_spl::token::transfer(
                 CpiContext::new(
                     ctx.accounts.token_program.to_account_info(),
                     anchor_spl::token::Transfer {
-                        from: buyer.to_account_info(),
-                        to: creator_royalties.to_account_info(),
-                        authority: authority.to_account_info(),
+                        from: buyer_token_account.to_account_info(),
+                        to: curve_reserve.to_account_info(),
+                        authority: buyer.to_account_info(),
                     },
                 ),
-                royalty_amount,
+                reserve_cut,
             )?;
-            
-            // Обновляем общие роялти
-            track.total_royalties_paid = track.total_royalties_paid.checked_add(royalty_amount).unwrap();
         }
         
-        // Выплачиваем платформе
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: buyer.to_account_info(),
-                    to: platform_fees.to_account_info(),
-                    authority: authority.to_account_info(),
-                },
-            ),
-            platform_fee,
+        // Купон артиста снижает итоговую цену
+        if let Some(coupon) = ctx.accounts.coupon.as_mut() {
+            require!(!track.has_curve, ErrorCode::InvalidCoupon);
+            require!(coupon.artist == track.creator, ErrorCode::InvalidCoupon);
+            require!(
+                coupon.track == Pubkey::default() || coupon.track == track.key(),
+                ErrorCode::InvalidCoupon
+            );
+            require!(
+                coupon.expires_at == 0 || current_time < coupon.expires_at,
+                ErrorCode::CouponExpired
+            );
+            require!(coupon.uses < coupon.max_uses, ErrorCode::CouponExhausted);
+            
+            // Погашения считаются по кошельку: раскрытый в транзакции код
+            // дает любому кошельку не больше max_uses_per_wallet скидок
+            let redemption = ctx
+                .accounts
+                .coupon_redemption
+                .as_mut()
+                .ok_or(ErrorCode::MissingCouponRedemption)?;
+            require!(
+                coupon.max_uses_per_wallet == 0 || redemption.uses < coupon.max_uses_per_wallet,
+                ErrorCode::CouponWalletLimit
+            );
+            redemption.coupon = coupon.key();
+            redemption.buyer = buyer.key();
+            redemption.uses = redemption.uses.checked_add(1).unwrap();
+            redemption.bump = ctx.bumps.coupon_redemption.unwrap();
+            
+            if coupon.code_hash != [0u8; 32] {
+                let code = coupon_code.as_ref().ok_or(ErrorCode::InvalidCouponCode)?;
+                require!(
+                    hashv(&[code.as_bytes()]).to_bytes() == coupon.code_hash,
+                    ErrorCode::InvalidCouponCode
+                );
+            }
+            
+            let original_price = track_price;
+            let discount = coupon.discount_for(original_price);
+            track_price = original_price - discount;
+            
+            coupon.uses = coupon.uses.checked_add(1).unwrap();
+            coupon.total_discount = coupon.total_discount.checked_add(discount).unwrap();
+            
+            emit!(CouponRedeemedEvent {
+                coupon: coupon.key(),
+                track: track.key(),
+                buyer: buyer.key(),
+                original_price,
+                discount,
+                uses: coupon.uses,
+                timestamp: current_time,
+            });
+        }
+
+        let (royalty_amount, platform_fee) = complete_purchase(
+            Purchase {
+                track_nft,
+                track,
+                license: license.as_mut(),
+                holder: buyer.to_account_info(),
+                buyer: buyer.to_account_info(),
+                buyer_token_account: buyer_token_account.to_account_info(),
+                artist_token_account: artist_token_account.to_account_info(),
+                creator_royalties: creator_royalties.to_account_info(),
+                treasury: treasury.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                franchise: ctx.accounts.franchise.as_mut(),
+                franchise_vault: ctx.accounts.franchise_vault.as_ref(),
+                edition_tree: ctx.accounts.edition_tree.as_mut(),
+                tree_config: ctx.accounts.tree_config.as_ref().map(|a| a.to_account_info()),
+                merkle_tree: ctx.accounts.merkle_tree.as_ref().map(|a| a.to_account_info()),
+                bubblegum_program: ctx.accounts.bubblegum_program.as_ref().map(|a| a.to_account_info()),
+                compression_program: ctx.accounts.compression_program.as_ref().map(|a| a.to_account_info()),
+                log_wrapper: ctx.accounts.log_wrapper.as_ref().map(|a| a.to_account_info()),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                copyright_claim: ctx.accounts.copyright_claim.as_mut(),
+                dispute_escrow: ctx.accounts.dispute_escrow.as_ref(),
+                pending_sale: ctx.accounts.pending_sale.as_mut(),
+                sale_escrow: ctx.accounts.sale_escrow.as_ref(),
+                leaderboard: ctx.accounts.leaderboard.as_ref(),
+                parent_accounts: ctx.remaining_accounts,
+            },
+            track_price - reserve_cut,
+            current_time,
         )?;
         
-        // Обновляем счетчик продаж
-        track.play_count = track.play_count.checked_add(1).unwrap();
+        // Сдвигаем кривую на следующий экземпляр
+        if let Some(bonding_curve) = ctx.accounts.bonding_curve.as_mut() {
+            bonding_curve.supply = bonding_curve.supply.checked_add(1).unwrap();
+            bonding_curve.reserve = bonding_curve.reserve.checked_add(reserve_cut).unwrap();
+            let license = license.as_mut().ok_or(ErrorCode::InvalidLicenseAccount)?;
+            license.price_paid = license.price_paid.checked_add(reserve_cut).unwrap();
+            track.price = bonding_curve.price_at(bonding_curve.supply);
+            
+            emit!(CurvePriceUpdatedEvent {
+                track: track.key(),
+                supply: bonding_curve.supply,
+                reserve: bonding_curve.reserve,
+                price: track.price,
+                timestamp: current_time,
+            });
+        }
         
         emit!(TrackPurchasedEvent {
             track: track.key(),
             buyer: buyer.key(),
-            artist: artist.key(),
+            artist: track.creator,
             price: track_price,
             royalty_amount,
             platform_fee,
-            timestamp: Clock::get()?.unix_timestamp,
+            timestamp: current_time,
         });
         
         Ok(())
     }
 
-    // Воспроизведение трека (для сбора аналитики)
-    pub fn play_track(ctx: Context<PlayTrack>, track_id: Pubkey) -> Result<()> {
+    // Покупка трека в подарок: лицензия и экземпляр выдаются получателю,
+    // платит и получает возврат отправитель.
+    // remaining_accounts: как в buy_track.
+    pub fn gift_track<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GiftTrack<'info>>,
+        message: String,
+    ) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
         let track = &mut ctx.accounts.track;
-        let listener = &ctx.accounts.listener;
+        let payer = &ctx.accounts.payer;
+        let recipient = &ctx.accounts.recipient;
+        let payer_token_account = &ctx.accounts.payer_token_account;
+        let artist_token_account = &ctx.accounts.artist_token_account;
+        let creator_royalties = &mut ctx.accounts.creator_royalties;
+        let treasury = &mut ctx.accounts.treasury;
+        let license = &mut ctx.accounts.license;
         
         require!(track.is_listed, ErrorCode::TrackNotListed);
+        require!(!track.taken_down, ErrorCode::TrackTakenDown);
+        require!(payer.key() != track.creator, ErrorCode::CreatorCannotBuy);
+        require!(
+            recipient.key() != payer.key() && recipient.key() != track.creator,
+            ErrorCode::InvalidRecipient
+        );
+        require!(message.len() <= MAX_GIFT_MESSAGE_LENGTH, ErrorCode::GiftMessageTooLong);
+        require!(!track.has_curve, ErrorCode::CurvePricedTrack);
         
-        // Обновляем счетчик воспроизведений
-        track.play_count = track.play_count.checked_add(1).unwrap();
+        let current_time = Clock::get()?.unix_timestamp;
         
-        emit!(TrackPlayedEvent {
+        // Allowlist-фаза дропа личная, подарки только в публичной продаже
+        if track.has_drop {
+            let drop_config = ctx
+                .accounts
+                .drop_config
+                .as_ref()
+                .ok_or(ErrorCode::MissingDropConfig)?;
+            require!(
+                drop_config.phase_at(current_time) == DropPhase::Public,
+                ErrorCode::DropPhaseClosed
+            );
+        }
+        
+        let track_price = track.price;
+        let (royalty_amount, platform_fee) = complete_purchase(
+            Purchase {
+                track_nft,
+                track,
+                license: license.as_mut(),
+                holder: recipient.to_account_info(),
+                buyer: payer.to_account_info(),
+                buyer_token_account: payer_token_account.to_account_info(),
+                artist_token_account: artist_token_account.to_account_info(),
+                creator_royalties: creator_royalties.to_account_info(),
+                treasury: treasury.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                franchise: ctx.accounts.franchise.as_mut(),
+                franchise_vault: ctx.accounts.franchise_vault.as_ref(),
+                edition_tree: ctx.accounts.edition_tree.as_mut(),
+                tree_config: ctx.accounts.tree_config.as_ref().map(|a| a.to_account_info()),
+                merkle_tree: ctx.accounts.merkle_tree.as_ref().map(|a| a.to_account_info()),
+                bubblegum_program: ctx.accounts.bubblegum_program.as_ref().map(|a| a.to_account_info()),
+                compression_program: ctx.accounts.compression_program.as_ref().map(|a| a.to_account_info()),
+                log_wrapper: ctx.accounts.log_wrapper.as_ref().map(|a| a.to_account_info()),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                copyright_claim: ctx.accounts.copyright_claim.as_mut(),
+                dispute_escrow: ctx.accounts.dispute_escrow.as_ref(),
+                pending_sale: ctx.accounts.pending_sale.as_mut(),
+                sale_escrow: ctx.accounts.sale_escrow.as_ref(),
+                leaderboard: ctx.accounts.leaderboard.as_ref(),
+                parent_accounts: ctx.remaining_accounts,
+            },
+            track_price,
+            current_time,
+        )?;
+        
+        emit!(TrackPurchasedEvent {
             track: track.key(),
-            listener: listener.key(),
-            timestamp: Clock::get()?.unix_timestamp,
+            buyer: recipient.key(),
+            artist: track.creator,
+            price: track_price,
+            royalty_amount,
+            platform_fee,
+            timestamp: current_time,
+        });
+        
+        emit!(TrackGiftedEvent {
+            track: track.key(),
+            payer: payer.key(),
+            recipient: recipient.key(),
+            price: track_price,
+            message,
+            timestamp: current_time,
         });
         
         Ok(())
     }
 
-    // Лайк трека
-    pub fn like_track(ctx: Context<LikeTrack>, track_id: Pubkey) -> Result<()> {
+    // Возврат покупки в пределах окна: средства из эскроу покупателю,
+    // экземпляр лицензии отзывается, сжатый экземпляр сжигается.
+    // Для сжатых экземпляров root, data_hash и creator_hash - данные листа Bubblegum,
+    // remaining_accounts - его Merkle-доказательство.
+    pub fn request_refund<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RequestRefund<'info>>,
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+    ) -> Result<()> {
         let track = &mut ctx.accounts.track;
-        let listener = &ctx.accounts.listener;
+        let pending_sale = &ctx.accounts.pending_sale;
+        let buyer = &ctx.accounts.buyer;
         
-        require!(track.is_listed, ErrorCode::TrackNotListed);
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time < pending_sale.refundable_until, ErrorCode::RefundWindowClosed);
         
-        // Обновляем счетчик лайков
-        track.like_count = track.like_count.checked_add(1).unwrap();
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
         
-        emit!(TrackLikedEvent {
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.sale_escrow.to_account_info(),
+                    to: ctx.accounts.buyer_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            pending_sale.price,
+        )?;
+        
+        // Отзываем экземпляр лицензии (у покупателя или получателя подарка)
+        if !pending_sale.compressed {
+            let license = ctx.accounts.license.as_mut().ok_or(ErrorCode::InvalidLicenseAccount)?;
+            require!(license.editions > 0, ErrorCode::LicenseNotOwned);
+            license.editions -= 1;
+            license.price_paid = license.price_paid.saturating_sub(pending_sale.price);
+            license.pending_refunds = license.pending_refunds.saturating_sub(1);
+            if license.editions == 0 && license.expires_at == 0 {
+                track.unique_buyers = track.unique_buyers.saturating_sub(1);
+            }
+        }
+        track.gross_revenue = track.gross_revenue.saturating_sub(pending_sale.price);
+        track.refund_count = track.refund_count.checked_add(1).unwrap();
+        
+        let week = chart_week_at(pending_sale.purchased_at);
+        if pending_sale.price >= CHART_MIN_SALE_PRICE && track.revert_chart_sale(week) {
+            if let Some(leaderboard) = ctx.accounts.leaderboard.as_ref() {
+                leaderboard
+                    .load_mut()?
+                    .record(week, track.key(), track.week_plays, track.week_sales);
+            }
+        }
+        
+        // Сжатый экземпляр сжигает делегат листа - PDA дерева.
+        // Переданный или переделегированный держателем экземпляр не возвращается.
+        if pending_sale.compressed {
+            let edition_tree = ctx
+                .accounts
+                .edition_tree
+                .as_ref()
+                .ok_or(ErrorCode::MissingEditionTree)?;
+            let tree_config = ctx.accounts.tree_config.as_ref().ok_or(ErrorCode::MissingEditionTree)?;
+            let merkle_tree = ctx.accounts.merkle_tree.as_ref().ok_or(ErrorCode::MissingEditionTree)?;
+            require!(merkle_tree.key() == edition_tree.merkle_tree, ErrorCode::InvalidEditionTree);
+            let holder = ctx.accounts.holder.as_ref().ok_or(ErrorCode::MissingEditionTree)?;
+            let bubblegum_program = ctx
+                .accounts
+                .bubblegum_program
+                .as_ref()
+                .ok_or(ErrorCode::MissingEditionTree)?;
+            let compression_program = ctx
+                .accounts
+                .compression_program
+                .as_ref()
+                .ok_or(ErrorCode::MissingEditionTree)?;
+            let log_wrapper = ctx.accounts.log_wrapper.as_ref().ok_or(ErrorCode::MissingEditionTree)?;
+            
+            let artist = edition_tree.artist;
+            let index_bytes = edition_tree.index.to_le_bytes();
+            let tree_seeds = &[b"edition_tree".as_ref(), artist.as_ref(), index_bytes.as_ref(), &[edition_tree.bump]];
+            let tree_signer = &[&tree_seeds[..]];
+            
+            let proof: Vec<(&AccountInfo<'info>, bool, bool)> = ctx
+                .remaining_accounts
+                .iter()
+                .map(|node| (node, false, false))
+                .collect();
+            
+            BurnCpiBuilder::new(&bubblegum_program.to_account_info())
+                .tree_config(&tree_config.to_account_info())
+                .leaf_owner(&holder.to_account_info(), false)
+                .leaf_delegate(&edition_tree.to_account_info(), true)
+                .merkle_tree(&merkle_tree.to_account_info())
+                .log_wrapper(&log_wrapper.to_account_info())
+                .compression_program(&compression_program.to_account_info())
+                .system_program(&ctx.accounts.system_program.to_account_info())
+                .root(root)
+                .data_hash(data_hash)
+                .creator_hash(creator_hash)
+                .nonce(pending_sale.leaf_index)
+                .index(pending_sale.leaf_index as u32)
+                .add_remaining_accounts(&proof)
+                .invoke_signed(tree_signer)?;
+        }
+        
+        emit!(PurchaseRefundedEvent {
             track: track.key(),
-            listener: listener.key(),
-            timestamp: Clock::get()?.unix_timestamp,
+            buyer: buyer.key(),
+            edition: pending_sale.edition,
+            amount: pending_sale.price,
+            timestamp: current_time,
         });
         
         Ok(())
     }
 
-    // Обновление цены трека
-    pub fn update_price(ctx: Context<UpdatePrice>, new_price: u64) -> Result<()> {
-        let track = &mut ctx.accounts.track;
-        let authority = &ctx.accounts.authority;
+    // Выплата выручки из эскроу после закрытия окна возврата (доступна любому).
+    // remaining_accounts: как в buy_track, пары родительских треков ремикса.
+    pub fn release_escrow<'info>(ctx: Context<'_, '_, 'info, 'info, ReleaseEscrow<'info>>) -> Result<()> {
+        let pending_sale = &ctx.accounts.pending_sale;
         
-        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time >= pending_sale.refundable_until, ErrorCode::RefundWindowOpen);
         
-        track.price = new_price;
+        // Экземпляр больше не возвращается и может быть передан
+        if !pending_sale.compressed {
+            let license = ctx.accounts.license.as_mut().ok_or(ErrorCode::InvalidLicenseAccount)?;
+            license.pending_refunds = license.pending_refunds.saturating_sub(1);
+        }
         
-        emit!(PriceUpdatedEvent {
-            track: track.key(),
-            new_price,
-            timestamp: Clock::get()?.unix_timestamp,
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        let track_nft_info = ctx.accounts.track_nft.to_account_info();
+        
+        distribute_sale_proceeds(
+            &mut ctx.accounts.track,
+            &mut ctx.accounts.track_nft,
+            SaleProceeds {
+                from: ctx.accounts.sale_escrow.to_account_info(),
+                authority: track_nft_info,
+                signer,
+                token_program: ctx.accounts.token_program.to_account_info(),
+                artist_token_account: ctx.accounts.artist_token_account.to_account_info(),
+                creator_royalties: ctx.accounts.creator_royalties.to_account_info(),
+                treasury: ctx.accounts.treasury.to_account_info(),
+                franchise: ctx.accounts.franchise.as_mut(),
+                franchise_vault: ctx.accounts.franchise_vault.as_ref(),
+                copyright_claim: ctx.accounts.copyright_claim.as_mut(),
+                dispute_escrow: ctx.accounts.dispute_escrow.as_ref(),
+                parent_accounts: ctx.remaining_accounts,
+            },
+            pending_sale.price,
+            pending_sale.royalty_amount,
+            pending_sale.platform_fee,
+            current_time,
+        )?;
+        
+        emit!(EscrowReleasedEvent {
+            track: pending_sale.track,
+            buyer: pending_sale.buyer,
+            edition: pending_sale.edition,
+            amount: pending_sale.price,
+            timestamp: current_time,
         });
         
         Ok(())
     }
 
-    // Обновление роялти
-    pub fn update_royalty(ctx: Context<UpdateRoyalty>, new_royalty: u8) -> Result<()> {
-        let track = &mut ctx.accounts.track;
-        let track_nft = &mut ctx.accounts.track_nft;
-        let authority = &ctx.accounts.authority;
+    // Создание дерева Bubblegum для сжатых экземпляров артиста. Деревья нумеруются,
+    // заполненное заменяется новым. Аккаунт дерева заранее выделяется клиентом
+    // под владельцем программы сжатия.
+    pub fn create_edition_tree(
+        ctx: Context<CreateEditionTree>,
+        tree_index: u32,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let edition_tree = &mut ctx.accounts.edition_tree;
+        let artist = &ctx.accounts.artist;
         
-        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
-        require!(new_royalty <= 50, ErrorCode::RoyaltyTooHigh);
+        require!(
+            max_depth > 0 && max_depth <= MAX_EDITION_TREE_DEPTH,
+            ErrorCode::InvalidEditionTree
+        );
         
-        track.royalty_percentage = new_royalty;
+        edition_tree.artist = artist.key();
+        edition_tree.index = tree_index;
+        edition_tree.merkle_tree = ctx.accounts.merkle_tree.key();
+        edition_tree.max_depth = max_depth;
+        edition_tree.max_buffer_size = max_buffer_size;
+        edition_tree.capacity = 1u64 << max_depth;
+        edition_tree.minted = 0;
+        edition_tree.bump = ctx.bumps.edition_tree;
         
-        emit!(RoyaltyUpdatedEvent {
-            track: track.key(),
-            new_royalty,
+        let index_bytes = tree_index.to_le_bytes();
+        let seeds = &[b"edition_tree".as_ref(), edition_tree.artist.as_ref(), index_bytes.as_ref(), &[edition_tree.bump]];
+        let signer = &[&seeds[..]];
+        
+        // Приватное дерево: выпускать экземпляры может только PDA дерева
+        CreateTreeConfigCpiBuilder::new(&ctx.accounts.bubblegum_program.to_account_info())
+            .tree_config(&ctx.accounts.tree_config.to_account_info())
+            .merkle_tree(&ctx.accounts.merkle_tree.to_account_info())
+            .payer(&artist.to_account_info())
+            .tree_creator(&edition_tree.to_account_info())
+            .log_wrapper(&ctx.accounts.log_wrapper.to_account_info())
+            .compression_program(&ctx.accounts.compression_program.to_account_info())
+            .system_program(&ctx.accounts.system_program.to_account_info())
+            .max_depth(max_depth)
+            .max_buffer_size(max_buffer_size)
+            .public(false)
+            .invoke_signed(signer)?;
+        
+        emit!(EditionTreeCreatedEvent {
+            artist: artist.key(),
+            index: tree_index,
+            merkle_tree: edition_tree.merkle_tree,
+            capacity: edition_tree.capacity,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
 
-    // Списание/снятие с продажи
-    pub fn toggle_listing(ctx: Context<ToggleListing>) -> Result<()> {
+    // Включение/выключение сжатых экземпляров для продаж трека. Повторное включение
+    // с новым деревом переводит продажи на него (после EditionTreeFull).
+    // Держатели сжатых экземпляров подтверждают доступ владением cNFT, а не лицензией.
+    pub fn set_compressed_editions(ctx: Context<SetCompressedEditions>, enabled: bool) -> Result<()> {
         let track = &mut ctx.accounts.track;
+        let edition_tree = &ctx.accounts.edition_tree;
         let authority = &ctx.accounts.authority;
         
         require!(authority.key() == track.creator, ErrorCode::Unauthorized);
         
-        track.is_listed = !track.is_listed;
-        
-        emit!(ListingToggledEvent {
-            track: track.key(),
-            is_listed: track.is_listed,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        if enabled {
+            // Выкуп кривой и лимиты дропа работают с лицензиями
+            require!(!track.has_curve, ErrorCode::CurvePricedTrack);
+            require!(!track.has_drop, ErrorCode::CompressedDropUnsupported);
+            require!(edition_tree.minted < edition_tree.capacity, ErrorCode::EditionTreeFull);
+            track.edition_tree = edition_tree.key();
+        }
+        track.compressed_editions = enabled;
         
         Ok(())
     }
 
-    // Выплата роялти создателям
-    pub fn pay_royalties(ctx: Context<PayRoyalties>) -> Result<()> {
+    // Параметры споров: арбитр и размер залога
+    pub fn set_dispute_params(ctx: Context<SetPlayOracle>, arbiter: Pubkey, claim_bond: u64) -> Result<()> {
         let track_nft = &mut ctx.accounts.track_nft;
-        let royalty_pool = &mut ctx.accounts.royalty_pool;
         let authority = &ctx.accounts.authority;
         
-        // Логика выплаты роялти из пула
-        // Здесь можно реализовать сложную логику распределения роялти
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(arbiter != Pubkey::default(), ErrorCode::InvalidDisputeParams);
         
-        emit!(RoyaltiesPaidEvent {
-            total_amount: royalty_pool.amount,
+        track_nft.arbiter = arbiter;
+        track_nft.claim_bond = claim_bond;
+        
+        Ok(())
+    }
+
+    // Подача жалобы на нарушение авторских прав (с залогом).
+    // До решения арбитра доля создателя в продажах уходит в эскроу.
+    pub fn file_claim(ctx: Context<FileClaim>, evidence_hash: [u8; 32]) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
+        let track = &mut ctx.accounts.track;
+        let claim = &mut ctx.accounts.copyright_claim;
+        let claimant = &ctx.accounts.claimant;
+        
+        require!(claimant.key() != track.creator, ErrorCode::Unauthorized);
+        
+        let bond = track_nft.claim_bond;
+        if bond > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.claimant_token_account.to_account_info(),
+                        to: ctx.accounts.dispute_escrow.to_account_info(),
+                        authority: claimant.to_account_info(),
+                    },
+                ),
+                bond,
+            )?;
+        }
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        claim.track = track.key();
+        claim.claimant = claimant.key();
+        claim.evidence_hash = evidence_hash;
+        claim.bond = bond;
+        claim.escrowed = 0;
+        claim.filed_at = current_time;
+        track.under_dispute = true;
+        
+        emit!(ClaimFiledEvent {
+            track: track.key(),
+            claimant: claimant.key(),
+            evidence_hash,
+            bond,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Решение арбитра: эскроу победителю, залог возвращается заявителю
+    // при удовлетворении жалобы и уходит в казну при отклонении.
+    pub fn resolve_claim(ctx: Context<ResolveClaim>, uphold: bool) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let track = &mut ctx.accounts.track;
+        let claim = &ctx.accounts.copyright_claim;
+        
+        require!(ctx.accounts.arbiter.key() == track_nft.arbiter, ErrorCode::Unauthorized);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        let escrow_winner = if uphold {
+            ctx.accounts.claimant_token_account.to_account_info()
+        } else {
+            ctx.accounts.creator_token_account.to_account_info()
+        };
+        let bond_destination = if uphold {
+            ctx.accounts.claimant_token_account.to_account_info()
+        } else {
+            ctx.accounts.treasury.to_account_info()
+        };
+        
+        for (destination, amount) in [(escrow_winner, claim.escrowed), (bond_destination, claim.bond)] {
+            if amount == 0 {
+                continue;
+            }
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.dispute_escrow.to_account_info(),
+                        to: destination,
+                        authority: track_nft.to_account_info(),
+                    },
+                    signer,
+                ),
+                amount,
+            )?;
+        }
+        if !uphold {
+            track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(claim.bond).unwrap();
+        }
+        
+        // Нарушающий трек снимается с продажи навсегда, отпечаток освобождается
+        // для правообладателя. Ему же уходит выручка продаж, еще ждущих
+        // в эскроу возврата (release_escrow платит на payout_wallet).
+        track.under_dispute = false;
+        if uphold {
+            track.is_listed = false;
+            track.taken_down = true;
+            track.payout_wallet = claim.claimant;
+            if track.fingerprint != [0u8; 32] {
+                let content_record = ctx
+                    .accounts
+                    .content_record
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingContentRecord)?;
+                if content_record.track == track.key() {
+                    content_record.track = Pubkey::default();
+                    content_record.creator = Pubkey::default();
+                }
+            }
+        }
+        
+        emit!(ClaimResolvedEvent {
+            track: track.key(),
+            claimant: claim.claimant,
+            upheld: uphold,
+            escrowed: claim.escrowed,
+            bond: claim.bond,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Условия аренды трека (duration = 0 отключает аренду)
+    pub fn set_rental_terms(
+        ctx: Context<SetRentalTerms>,
+        rental_price: u64,
+        rental_duration: i64,
+    ) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(
+            (0..=MAX_RENTAL_DURATION).contains(&rental_duration),
+            ErrorCode::InvalidRentalTerms
+        );
+        require!(
+            rental_duration == 0 || rental_price <= track.price,
+            ErrorCode::InvalidRentalTerms
+        );
+        
+        track.rental_price = rental_price;
+        track.rental_duration = rental_duration;
+        
+        emit!(RentalTermsUpdatedEvent {
+            track: track.key(),
+            rental_price,
+            rental_duration,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Разрешение передавать бессрочные лицензии трека
+    pub fn set_transferable(ctx: Context<SetRentalTerms>, transferable: bool) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        
+        track.transferable = transferable;
+        
+        Ok(())
+    }
+
+    // Передача одного экземпляра бессрочной лицензии другому кошельку.
+    // Лист сжатого экземпляра остается квитанцией исходной покупки.
+    pub fn transfer_license(ctx: Context<TransferLicense>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let license = &mut ctx.accounts.license;
+        let recipient_license = &mut ctx.accounts.recipient_license;
+        let owner = &ctx.accounts.owner;
+        let recipient = &ctx.accounts.recipient;
+        
+        require!(track.transferable, ErrorCode::LicenseNotTransferable);
+        require!(
+            license.editions > 0 && license.expires_at == 0,
+            ErrorCode::LicenseNotOwned
+        );
+        // Пока покупка возвратна, передача позволила бы вернуть деньги за отданный экземпляр
+        require!(license.pending_refunds == 0, ErrorCode::LicenseRefundPending);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        // Цена экземпляра переходит вместе с ним
+        let edition_price = license.price_paid / license.editions as u64;
+        license.editions -= 1;
+        license.price_paid = license.price_paid.saturating_sub(edition_price);
+        
+        // Аренда получателя становится бессрочной лицензией; плата за аренду
+        // не входит в стоимость экземпляров (на нее опираются выкуп и возвраты)
+        if recipient_license.editions == 0 {
+            track.unique_buyers = track.unique_buyers.checked_add(1).unwrap();
+        }
+        if recipient_license.expires_at != 0 {
+            recipient_license.price_paid = 0;
+        }
+        recipient_license.track = track.key();
+        recipient_license.owner = recipient.key();
+        recipient_license.price_paid = recipient_license.price_paid.checked_add(edition_price).unwrap();
+        recipient_license.purchased_at = current_time;
+        recipient_license.editions = recipient_license.editions.checked_add(1).unwrap();
+        recipient_license.expires_at = 0;
+        
+        let editions_left = license.editions;
+        if editions_left == 0 {
+            track.unique_buyers = track.unique_buyers.saturating_sub(1);
+            license.close(owner.to_account_info())?;
+        }
+        
+        emit!(LicenseTransferredEvent {
+            track: track.key(),
+            from: owner.key(),
+            to: recipient.key(),
+            editions_left,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Аренда трека: лицензия с истечением (повторная аренда продлевает доступ).
+    // Окно возврата на аренду не распространяется: доступ открывается сразу.
+    // remaining_accounts: как в buy_track, пары родительских треков ремикса.
+    pub fn rent_track<'info>(ctx: Context<'_, '_, 'info, 'info, RentTrack<'info>>) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let track = &mut ctx.accounts.track;
+        let buyer = &ctx.accounts.buyer;
+        let buyer_token_account = &ctx.accounts.buyer_token_account;
+        let license = &mut ctx.accounts.license;
+        
+        require!(track.is_listed, ErrorCode::TrackNotListed);
+        require!(!track.taken_down, ErrorCode::TrackTakenDown);
+        require!(track.rental_duration > 0, ErrorCode::RentalsDisabled);
+        require!(!track.has_drop, ErrorCode::DropPhaseClosed);
+        require!(buyer.key() != track.creator, ErrorCode::CreatorCannotBuy);
+        require!(
+            license.editions == 0 || license.expires_at != 0,
+            ErrorCode::AlreadyLicensed
+        );
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        let rental_price = track.rental_price;
+        let platform_fee = calculate_bps(rental_price, track_nft.platform_fee_bps);
+        let mut artist_amount = rental_price.checked_sub(platform_fee).unwrap();
+        
+        let upstream_paid = pay_upstream_royalties(
+            track,
+            ctx.remaining_accounts,
+            &track_nft.payment_mint,
+            &buyer_token_account.to_account_info(),
+            &buyer.to_account_info(),
+            &[],
+            &ctx.accounts.token_program.to_account_info(),
+            artist_amount,
+            current_time,
+        )?;
+        artist_amount = artist_amount.checked_sub(upstream_paid).unwrap();
+        
+        if track.has_franchise {
+            let franchise_cut = pay_franchise_cut(
+                ctx.accounts.franchise.as_mut(),
+                ctx.accounts.franchise_vault.as_ref(),
+                &buyer_token_account.to_account_info(),
+                &buyer.to_account_info(),
+                &[],
+                &ctx.accounts.token_program.to_account_info(),
+                artist_amount,
+            )?;
+            artist_amount = artist_amount.checked_sub(franchise_cut).unwrap();
+        }
+        
+        if track.under_dispute {
+            escrow_disputed_proceeds(
+                ctx.accounts.copyright_claim.as_mut(),
+                ctx.accounts.dispute_escrow.as_ref(),
+                &buyer_token_account.to_account_info(),
+                &buyer.to_account_info(),
+                &[],
+                &ctx.accounts.token_program.to_account_info(),
+                artist_amount,
+            )?;
+        } else {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: buyer_token_account.to_account_info(),
+                        to: ctx.accounts.artist_token_account.to_account_info(),
+                        authority: buyer.to_account_info(),
+                    },
+                ),
+                artist_amount,
+            )?;
+        }
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: buyer_token_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: buyer.to_account_info(),
+                },
+            ),
+            platform_fee,
+        )?;
+        track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(platform_fee).unwrap();
+        
+        // Продление считается от текущего момента или от действующего срока
+        let access_from = license.expires_at.max(current_time);
+        license.track = track.key();
+        license.owner = buyer.key();
+        license.price_paid = license.price_paid.checked_add(rental_price).unwrap();
+        license.purchased_at = current_time;
+        license.expires_at = access_from.checked_add(track.rental_duration).unwrap();
+        
+        emit!(TrackRentedEvent {
+            track: track.key(),
+            renter: buyer.key(),
+            price: rental_price,
+            platform_fee,
+            expires_at: license.expires_at,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Проверка доступа к треку (для симуляции из API)
+    pub fn is_access_valid(ctx: Context<IsAccessValid>) -> Result<bool> {
+        let license_info = ctx.accounts.license.to_account_info();
+        if license_info.data_is_empty() {
+            return Ok(false);
+        }
+        
+        let license = License::try_deserialize(&mut &license_info.try_borrow_data()?[..])?;
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        Ok(license_has_access(&license, current_time))
+    }
+
+    // Закрытие истекшей арендной лицензии, рента возвращается владельцу
+    pub fn close_expired_license(ctx: Context<CloseExpiredLicense>) -> Result<()> {
+        let license = &ctx.accounts.license;
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        require!(
+            license.expires_at != 0 && !license_has_access(license, current_time),
+            ErrorCode::LicenseNotExpired
+        );
+        
+        emit!(LicenseClosedEvent {
+            track: license.track,
+            owner: license.owner,
+            expired_at: license.expires_at,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Лицензия на ремикс: создатель родительского трека одобряет долю
+    // и число производных треков (0 - без ограничения)
+    pub fn grant_remix_license(
+        ctx: Context<GrantRemixLicense>,
+        remixer: Pubkey,
+        share_bps: u16,
+        max_derivatives: u32,
+    ) -> Result<()> {
+        let parent_track = &ctx.accounts.parent_track;
+        let remix_license = &mut ctx.accounts.remix_license;
+        let creator = &ctx.accounts.creator;
+        
+        require!(creator.key() == parent_track.creator, ErrorCode::Unauthorized);
+        require!(share_bps <= MAX_UPSTREAM_SHARE_BPS, ErrorCode::UpstreamShareTooHigh);
+        
+        remix_license.parent_track = parent_track.key();
+        remix_license.remixer = remixer;
+        remix_license.share_bps = share_bps;
+        remix_license.derivatives = 0;
+        remix_license.max_derivatives = max_derivatives;
+        remix_license.granted_at = Clock::get()?.unix_timestamp;
+        
+        emit!(RemixLicenseGrantedEvent {
+            parent_track: parent_track.key(),
+            remixer,
+            share_bps,
+            max_derivatives,
+            timestamp: remix_license.granted_at,
+        });
+        
+        Ok(())
+    }
+
+    // Отзыв лицензии: уже выпущенные ремиксы сохраняют свои доли
+    pub fn revoke_remix_license(ctx: Context<RevokeRemixLicense>) -> Result<()> {
+        let remix_license = &ctx.accounts.remix_license;
+        
+        require!(
+            ctx.accounts.creator.key() == ctx.accounts.parent_track.creator,
+            ErrorCode::Unauthorized
+        );
+        
+        emit!(RemixLicenseRevokedEvent {
+            parent_track: remix_license.parent_track,
+            remixer: remix_license.remixer,
+            derivatives: remix_license.derivatives,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Купон на скидку для всех треков артиста (track = default) или одного трека.
+    // code_hash = sha256(code), нулевой хэш - купон без секретного кода.
+    // max_uses_per_wallet ограничивает погашения одним кошельком (0 - без лимита).
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_coupon(
+        ctx: Context<CreateCoupon>,
+        coupon_id: u64,
+        track: Pubkey,
+        kind: DiscountKind,
+        value: u64,
+        max_uses: u32,
+        max_uses_per_wallet: u32,
+        expires_at: i64,
+        code_hash: [u8; 32],
+    ) -> Result<()> {
+        let coupon = &mut ctx.accounts.coupon;
+        let artist = &ctx.accounts.artist;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        require!(value > 0 && max_uses > 0, ErrorCode::InvalidCoupon);
+        require!(
+            kind != DiscountKind::Percentage || value <= 10000,
+            ErrorCode::InvalidCoupon
+        );
+        require!(expires_at == 0 || expires_at > current_time, ErrorCode::InvalidCoupon);
+        
+        // Купон на конкретный трек выпускает только его создатель
+        if track != Pubkey::default() {
+            let track_account = ctx
+                .accounts
+                .track
+                .as_ref()
+                .ok_or(ErrorCode::InvalidCoupon)?;
+            require!(track_account.key() == track, ErrorCode::InvalidCoupon);
+            require!(track_account.creator == artist.key(), ErrorCode::Unauthorized);
+        }
+        
+        coupon.artist = artist.key();
+        coupon.coupon_id = coupon_id;
+        coupon.track = track;
+        coupon.kind = kind;
+        coupon.value = value;
+        coupon.max_uses = max_uses;
+        coupon.max_uses_per_wallet = max_uses_per_wallet;
+        coupon.uses = 0;
+        coupon.expires_at = expires_at;
+        coupon.code_hash = code_hash;
+        coupon.total_discount = 0;
+        coupon.created_at = current_time;
+        coupon.bump = ctx.bumps.coupon;
+        
+        emit!(CouponCreatedEvent {
+            coupon: coupon.key(),
+            artist: artist.key(),
+            track,
+            kind,
+            value,
+            max_uses,
+            max_uses_per_wallet,
+            expires_at,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Закрытие купона артистом, рента возвращается ему
+    pub fn close_coupon(ctx: Context<CloseCoupon>) -> Result<()> {
+        let coupon = &ctx.accounts.coupon;
+        
+        emit!(CouponClosedEvent {
+            coupon: coupon.key(),
+            artist: coupon.artist,
+            uses: coupon.uses,
+            total_discount: coupon.total_discount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Перевод трека на цену по кривой (только до первой продажи).
+    // reserve_bps цены каждой продажи остается в резерве для выкупа экземпляров.
+    pub fn configure_bonding_curve(
+        ctx: Context<ConfigureBondingCurve>,
+        kind: CurveKind,
+        base_price: u64,
+        slope: u64,
+        reserve_bps: u16,
+    ) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(
+            track.sales_count == 0 && !track.has_drop && !track.compressed_editions,
+            ErrorCode::InvalidBondingCurve
+        );
+        require!(base_price > 0, ErrorCode::InvalidBondingCurve);
+        require!(
+            kind != CurveKind::Exponential || slope <= MAX_CURVE_GROWTH_BPS,
+            ErrorCode::InvalidBondingCurve
+        );
+        require!(reserve_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidBondingCurve);
+        
+        bonding_curve.track = track.key();
+        bonding_curve.kind = kind;
+        bonding_curve.base_price = base_price;
+        bonding_curve.slope = slope;
+        bonding_curve.reserve_bps = reserve_bps;
+        bonding_curve.supply = 0;
+        bonding_curve.reserve = 0;
+        bonding_curve.buybacks = 0;
+        bonding_curve.bump = ctx.bumps.bonding_curve;
+        
+        track.has_curve = true;
+        track.price = base_price;
+        
+        emit!(BondingCurveConfiguredEvent {
+            track: track.key(),
+            kind,
+            base_price,
+            slope,
+            reserve_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Выкуп экземпляра резервом кривой по цене последней продажи * reserve_bps.
+    // Выкуп всегда идет с вершины кривой, поэтому резерв покрывает все экземпляры.
+    pub fn sell_to_curve(ctx: Context<SellToCurve>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let license = &mut ctx.accounts.license;
+        let seller = &ctx.accounts.seller;
+        
+        require!(
+            license.editions > 0 && license.expires_at == 0,
+            ErrorCode::LicenseNotOwned
+        );
+        require!(bonding_curve.supply > 0, ErrorCode::CurveReserveEmpty);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        let last_price = bonding_curve.price_at(bonding_curve.supply - 1);
+        let payout = calculate_bps(last_price, bonding_curve.reserve_bps).min(bonding_curve.reserve);
+        require!(payout > 0, ErrorCode::CurveReserveEmpty);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.curve_reserve.to_account_info(),
+                    to: ctx.accounts.seller_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            payout,
+        )?;
+        
+        bonding_curve.supply -= 1;
+        bonding_curve.reserve -= payout;
+        bonding_curve.buybacks = bonding_curve.buybacks.checked_add(1).unwrap();
+        track.price = bonding_curve.price_at(bonding_curve.supply);
+        
+        // Экземпляр возвращается кривой вместе со своей долей цены
+        let edition_price = license.price_paid / license.editions as u64;
+        license.editions -= 1;
+        license.price_paid = license.price_paid.saturating_sub(edition_price);
+        
+        let editions_left = license.editions;
+        if editions_left == 0 {
+            track.unique_buyers = track.unique_buyers.saturating_sub(1);
+            license.close(seller.to_account_info())?;
+        }
+        
+        emit!(CurveBuybackEvent {
+            track: track.key(),
+            seller: seller.key(),
+            payout,
+            supply: bonding_curve.supply,
+            reserve: bonding_curve.reserve,
+            price: track.price,
+            editions_left,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Настройка фаз дропа трека (allowlist-пресейл, затем публичная продажа).
+    // presale_supply ограничивает экземпляры пресейла (0 - без лимита).
+    #[allow(clippy::too_many_arguments)]
+    pub fn configure_drop(
+        ctx: Context<ConfigureDrop>,
+        allowlist_root: [u8; 32],
+        presale_price: u64,
+        max_per_wallet: u32,
+        presale_supply: u64,
+        allowlist_start: i64,
+        allowlist_end: i64,
+        public_start: i64,
+        public_end: i64,
+    ) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let drop_config = &mut ctx.accounts.drop_config;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(!track.has_curve, ErrorCode::CurvePricedTrack);
+        require!(!track.compressed_editions, ErrorCode::CompressedDropUnsupported);
+        require!(max_per_wallet > 0, ErrorCode::InvalidDropSchedule);
+        require!(
+            allowlist_start < allowlist_end
+                && allowlist_end <= public_start
+                && (public_end == 0 || public_start < public_end),
+            ErrorCode::InvalidDropSchedule
+        );
+        
+        drop_config.track = track.key();
+        drop_config.allowlist_root = allowlist_root;
+        drop_config.presale_price = presale_price;
+        drop_config.max_per_wallet = max_per_wallet;
+        drop_config.presale_supply = presale_supply;
+        drop_config.allowlist_start = allowlist_start;
+        drop_config.allowlist_end = allowlist_end;
+        drop_config.public_start = public_start;
+        drop_config.public_end = public_end;
+        track.has_drop = true;
+        
+        emit!(DropConfiguredEvent {
+            track: track.key(),
+            allowlist_root,
+            presale_price,
+            max_per_wallet,
+            presale_supply,
+            allowlist_start,
+            allowlist_end,
+            public_start,
+            public_end,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
+
+    // Отключение дропа: трек продается по обычной цене
+    pub fn close_drop(ctx: Context<CloseDrop>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        
+        track.has_drop = false;
+        
+        Ok(())
+    }
+
+    // Создание франшизы трека: минт долей с фиксированным предложением
+    pub fn create_franchise(
+        ctx: Context<CreateFranchise>,
+        total_shares: u64,
+        share_price: u64,
+        revenue_share_bps: u16,
+    ) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let creator = &ctx.accounts.creator;
+        
+        require!(creator.key() == track.creator, ErrorCode::Unauthorized);
+        require!(total_shares > 0, ErrorCode::InvalidFranchiseParams);
+        require!(
+            revenue_share_bps > 0 && revenue_share_bps <= MAX_FRANCHISE_SHARE_BPS,
+            ErrorCode::InvalidFranchiseParams
+        );
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        // Выпускаем все доли в хранилище и отзываем право минта
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.share_vault.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            total_shares,
+        )?;
+        anchor_spl::token::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::SetAuthority {
+                    current_authority: ctx.accounts.track_nft.to_account_info(),
+                    account_or_mint: ctx.accounts.share_mint.to_account_info(),
+                },
+                signer,
+            ),
+            anchor_spl::token::spl_token::instruction::AuthorityType::MintTokens,
+            None,
+        )?;
+        
+        let franchise = &mut ctx.accounts.franchise;
+        franchise.track = track.key();
+        franchise.creator = track.creator;
+        franchise.share_mint = ctx.accounts.share_mint.key();
+        franchise.total_shares = total_shares;
+        franchise.shares_sold = 0;
+        franchise.staked_shares = 0;
+        franchise.share_price = share_price;
+        franchise.revenue_share_bps = revenue_share_bps;
+        franchise.acc_revenue_per_share = 0;
+        franchise.total_revenue = 0;
+        franchise.unallocated_revenue = 0;
+        franchise.is_active = true;
+        
+        track.has_franchise = true;
+        
+        emit!(FranchiseCreatedEvent {
+            track: track.key(),
+            share_mint: franchise.share_mint,
+            total_shares,
+            share_price,
+            revenue_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Первичная продажа долей франшизы
+    pub fn purchase_shares(ctx: Context<PurchaseShares>, shares: u64) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let franchise = &mut ctx.accounts.franchise;
+        let holding = &mut ctx.accounts.holding;
+        let buyer = &ctx.accounts.buyer;
+        
+        require!(franchise.is_active, ErrorCode::FranchiseInactive);
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        require!(
+            franchise.shares_sold.checked_add(shares).unwrap() <= franchise.total_shares,
+            ErrorCode::InsufficientShares
+        );
+        
+        let cost = franchise.share_price.checked_mul(shares).unwrap();
+        let platform_fee = calculate_bps(cost, track_nft.platform_fee_bps);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.buyer_token_account.to_account_info(),
+                    to: ctx.accounts.artist_token_account.to_account_info(),
+                    authority: buyer.to_account_info(),
+                },
+            ),
+            cost.checked_sub(platform_fee).unwrap(),
+        )?;
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.buyer_token_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: buyer.to_account_info(),
+                },
+            ),
+            platform_fee,
+        )?;
+        track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(platform_fee).unwrap();
+        
+        // Доли остаются в хранилище и учитываются в позиции держателя
+        if holding.holder == Pubkey::default() {
+            holding.franchise = franchise.key();
+            holding.holder = buyer.key();
+        }
+        franchise.settle_holding(holding);
+        holding.shares = holding.shares.checked_add(shares).unwrap();
+        holding.reward_debt = franchise.reward_debt_for(holding.shares);
+        franchise.shares_sold = franchise.shares_sold.checked_add(shares).unwrap();
+        franchise.staked_shares = franchise.staked_shares.checked_add(shares).unwrap();
+        
+        emit!(SharesPurchasedEvent {
+            track: franchise.track,
+            buyer: buyer.key(),
+            shares,
+            cost,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Вывод долей на кошелек (выведенные доли не получают доход)
+    pub fn withdraw_shares(ctx: Context<MoveShares>, shares: u64) -> Result<()> {
+        let franchise = &mut ctx.accounts.franchise;
+        let holding = &mut ctx.accounts.holding;
+        
+        require!(shares > 0 && holding.shares >= shares, ErrorCode::InsufficientShares);
+        
+        franchise.settle_holding(holding);
+        holding.shares = holding.shares.checked_sub(shares).unwrap();
+        holding.reward_debt = franchise.reward_debt_for(holding.shares);
+        franchise.staked_shares = franchise.staked_shares.checked_sub(shares).unwrap();
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.share_vault.to_account_info(),
+                    to: ctx.accounts.holder_share_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            shares,
+        )?;
+        
+        Ok(())
+    }
+
+    // Возврат долей с кошелька в позицию для получения дохода
+    pub fn deposit_shares(ctx: Context<MoveShares>, shares: u64) -> Result<()> {
+        let franchise = &mut ctx.accounts.franchise;
+        let holding = &mut ctx.accounts.holding;
+        let holder = &ctx.accounts.holder;
+        
+        require!(shares > 0, ErrorCode::InvalidAmount);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.holder_share_account.to_account_info(),
+                    to: ctx.accounts.share_vault.to_account_info(),
+                    authority: holder.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+        
+        franchise.settle_holding(holding);
+        holding.shares = holding.shares.checked_add(shares).unwrap();
+        holding.reward_debt = franchise.reward_debt_for(holding.shares);
+        franchise.staked_shares = franchise.staked_shares.checked_add(shares).unwrap();
+        
+        Ok(())
+    }
+
+    // Получение накопленного дохода держателем долей
+    pub fn claim_franchise_revenue(ctx: Context<ClaimFranchiseRevenue>) -> Result<()> {
+        let franchise = &mut ctx.accounts.franchise;
+        let holding = &mut ctx.accounts.holding;
+        let holder = &ctx.accounts.holder;
+        
+        franchise.settle_holding(holding);
+        let amount = holding.unclaimed;
+        require!(amount > 0, ErrorCode::NoRoyaltiesToClaim);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.franchise_vault.to_account_info(),
+                    to: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        holding.unclaimed = 0;
+        holding.total_claimed = holding.total_claimed.checked_add(amount).unwrap();
+        
+        emit!(FranchiseRevenueClaimedEvent {
+            track: franchise.track,
+            holder: holder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Доход, приходящийся на непроданные и выведенные доли, получает создатель
+    pub fn claim_unallocated_revenue(ctx: Context<ClaimUnallocatedRevenue>) -> Result<()> {
+        let franchise = &mut ctx.accounts.franchise;
+        let creator = &ctx.accounts.creator;
+        
+        require!(creator.key() == franchise.creator, ErrorCode::Unauthorized);
+        let amount = franchise.unallocated_revenue;
+        require!(amount > 0, ErrorCode::NoRoyaltiesToClaim);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.franchise_vault.to_account_info(),
+                    to: ctx.accounts.creator_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        franchise.unallocated_revenue = 0;
+        
+        emit!(FranchiseRevenueClaimedEvent {
+            track: franchise.track,
+            holder: creator.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Приостановка или возобновление продажи долей
+    pub fn set_franchise_active(ctx: Context<SetFranchiseActive>, is_active: bool) -> Result<()> {
+        let franchise = &mut ctx.accounts.franchise;
+        let creator = &ctx.accounts.creator;
+        
+        require!(creator.key() == franchise.creator, ErrorCode::Unauthorized);
+        
+        franchise.is_active = is_active;
+        
+        Ok(())
+    }
+
+    // Создание альбома артиста
+    pub fn create_album(
+        ctx: Context<CreateAlbum>,
+        album_id: u64,
+        title: String,
+        price: u64,
+    ) -> Result<()> {
+        let album = &mut ctx.accounts.album;
+        let artist = &ctx.accounts.artist;
+        
+        require!(title.len() <= MAX_ALBUM_TITLE_LEN, ErrorCode::AlbumTitleTooLong);
+        
+        album.artist = artist.key();
+        album.album_id = album_id;
+        album.title = title;
+        album.price = price;
+        album.tracks = Vec::new();
+        album.is_listed = false;
+        album.sales_count = 0;
+        
+        emit!(AlbumCreatedEvent {
+            album: album.key(),
+            artist: artist.key(),
+            price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Добавление трека в конец треклиста альбома. У треков нет NFT-минта
+    // (лицензии - PDA), поэтому членство не проверяется через коллекцию Metaplex.
+    pub fn add_album_track(ctx: Context<AddAlbumTrack>) -> Result<()> {
+        let album = &mut ctx.accounts.album;
+        let track = &ctx.accounts.track;
+        let artist = &ctx.accounts.artist;
+        
+        require!(artist.key() == album.artist, ErrorCode::Unauthorized);
+        require!(track.creator == album.artist, ErrorCode::Unauthorized);
+        require!(album.tracks.len() < MAX_ALBUM_TRACKS, ErrorCode::AlbumFull);
+        require!(
+            !album.tracks.iter().any(|t| t.track == track.key()),
+            ErrorCode::TrackAlreadyInAlbum
+        );
+        
+        album.tracks.push(AlbumTrack { track: track.key() });
+        
+        emit!(AlbumTrackAddedEvent {
+            album: album.key(),
+            track: track.key(),
+            position: (album.tracks.len() - 1) as u8,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Удаление трека из альбома (порядок остальных сохраняется)
+    pub fn remove_album_track(ctx: Context<UpdateAlbum>, position: u8) -> Result<()> {
+        let album = &mut ctx.accounts.album;
+        let artist = &ctx.accounts.artist;
+        
+        require!(artist.key() == album.artist, ErrorCode::Unauthorized);
+        require!((position as usize) < album.tracks.len(), ErrorCode::InvalidAlbumPosition);
+        
+        let removed = album.tracks.remove(position as usize);
+        
+        emit!(AlbumTrackRemovedEvent {
+            album: album.key(),
+            track: removed.track,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Цена альбома и выставление на продажу
+    pub fn update_album(ctx: Context<UpdateAlbum>, price: u64, is_listed: bool) -> Result<()> {
+        let album = &mut ctx.accounts.album;
+        let artist = &ctx.accounts.artist;
+        
+        require!(artist.key() == album.artist, ErrorCode::Unauthorized);
+        require!(!is_listed || !album.tracks.is_empty(), ErrorCode::AlbumEmpty);
+        
+        album.price = price;
+        album.is_listed = is_listed;
+        
+        emit!(AlbumUpdatedEvent {
+            album: album.key(),
+            price,
+            is_listed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Покупка альбома: лицензии на все треки в одной транзакции.
+    // Альбомная скидка делится между треками сразу, окно возврата не действует.
+    // remaining_accounts: пары (track, license PDA) в порядке треклиста.
+    pub fn buy_album<'info>(ctx: Context<'_, '_, 'info, 'info, BuyAlbum<'info>>) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let album = &mut ctx.accounts.album;
+        let buyer = &ctx.accounts.buyer;
+        
+        require!(album.is_listed, ErrorCode::TrackNotListed);
+        require!(buyer.key() != album.artist, ErrorCode::CreatorCannotBuy);
+        require!(
+            ctx.remaining_accounts.len() == album.tracks.len() * 2,
+            ErrorCode::InvalidAlbumAccounts
+        );
+        
+        let album_price = album.price;
+        let platform_fee = calculate_bps(album_price, track_nft.platform_fee_bps);
+        let artist_amount = album_price.checked_sub(platform_fee).unwrap();
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.buyer_token_account.to_account_info(),
+                    to: ctx.accounts.artist_token_account.to_account_info(),
+                    authority: buyer.to_account_info(),
+                },
+            ),
+            artist_amount,
+        )?;
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.buyer_token_account.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: buyer.to_account_info(),
+                },
+            ),
+            platform_fee,
+        )?;
+        track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(platform_fee).unwrap();
+        
+        // Сумма цен треков по отдельности (для распределения выручки и скидки)
+        let mut tracks: Vec<Account<'info, Track>> = Vec::with_capacity(album.tracks.len());
+        let mut individual_total: u64 = 0;
+        for (i, album_track) in album.tracks.iter().enumerate() {
+            let track_info = &ctx.remaining_accounts[i * 2];
+            require!(track_info.key() == album_track.track, ErrorCode::InvalidAlbumAccounts);
+            let track: Account<'info, Track> = Account::try_from(track_info)?;
+            require!(track.is_listed, ErrorCode::TrackNotListed);
+            require!(!track.has_drop, ErrorCode::DropPhaseClosed);
+            require!(!track.has_franchise, ErrorCode::FranchiseTrackInAlbum);
+            // Выручка альбома уходит одним переводом на кошелек артиста альбома,
+            // поэтому треки с перенаправленной выплатой (в т.ч. каталог мемориала
+            // после redirect_track_payout) в альбомах не продаются - только по одному
+            require!(track.payout_wallet == album.artist, ErrorCode::PayoutRedirected);
+            require!(track.upstream.is_empty(), ErrorCode::RemixTrackInAlbum);
+            require!(!track.under_dispute, ErrorCode::TrackUnderDispute);
+            require!(!track.taken_down, ErrorCode::TrackTakenDown);
+            require!(!track.has_curve, ErrorCode::CurvePricedTrack);
+            individual_total = individual_total.checked_add(track.price).unwrap();
+            tracks.push(track);
+        }
+        // Альбом обязан быть дешевле своих треков по отдельности
+        require!(album_price < individual_total, ErrorCode::AlbumPriceTooHigh);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        for (i, track) in tracks.iter_mut().enumerate() {
+            // Доля цены альбома пропорционально цене трека
+            let allocated = (album_price as u128)
+                .checked_mul(track.price as u128)
+                .unwrap()
+                .checked_div(individual_total as u128)
+                .unwrap() as u64;
+            
+            let is_new_holder = issue_license(
+                &ctx.remaining_accounts[i * 2 + 1],
+                &track.key(),
+                &buyer.key(),
+                &buyer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                allocated,
+                current_time,
+            )?;
+            
+            track.sales_count = track.sales_count.checked_add(1).unwrap();
+            track.gross_revenue = track.gross_revenue.checked_add(allocated).unwrap();
+            if is_new_holder {
+                track.unique_buyers = track.unique_buyers.checked_add(1).unwrap();
+            }
+            track.exit(&crate::ID)?;
+        }
+        
+        album.sales_count = album.sales_count.checked_add(1).unwrap();
+        
+        emit!(AlbumPurchasedEvent {
+            album: album.key(),
+            buyer: buyer.key(),
+            price: album_price,
+            individual_total,
+            platform_fee,
+            track_count: album.tracks.len() as u8,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Миграция аккаунта трека на текущую раскладку.
+    // Старые треки считали продажи в play_count, поэтому исторические
+    // значения продаж передаются из индексатора TrackPurchasedEvent.
+    pub fn migrate_track(
+        ctx: Context<MigrateTrack>,
+        sales_count: u64,
+        gross_revenue: u64,
+        unique_buyers: u64,
+    ) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
+        let track_info = ctx.accounts.track.to_account_info();
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(track_info.owner == &crate::ID, ErrorCode::InvalidTrackAccount);
+        require!(unique_buyers <= sales_count, ErrorCode::InvalidMigrationData);
+        
+        // Увеличиваем аккаунт до нового размера (новые поля заполняются нулями)
+        if track_info.data_len() < TRACK_ACCOUNT_SPACE {
+            let rent_needed = Rent::get()?
+                .minimum_balance(TRACK_ACCOUNT_SPACE)
+                .saturating_sub(track_info.lamports());
+            if rent_needed > 0 {
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: authority.to_account_info(),
+                            to: track_info.clone(),
+                        },
+                    ),
+                    rent_needed,
+                )?;
+            }
+            track_info.realloc(TRACK_ACCOUNT_SPACE, true)?;
+        }
+        
+        let mut data = track_info.try_borrow_mut_data()?;
+        let mut track = Track::try_deserialize(&mut &data[..])?;
+        require!(track.layout_version < TRACK_LAYOUT_VERSION, ErrorCode::TrackAlreadyMigrated);
+        
+        // Отделяем продажи от воспроизведений (только для раскладки v0)
+        if track.layout_version == 0 {
+            track.play_count = track.play_count.saturating_sub(sales_count);
+            track.sales_count = sales_count;
+            track.gross_revenue = gross_revenue;
+            track.unique_buyers = unique_buyers;
+        }
+        // Выплаты по умолчанию идут создателю (до раскладки v6)
+        if track.payout_wallet == Pubkey::default() {
+            track.payout_wallet = track.creator;
+        }
+        track.layout_version = TRACK_LAYOUT_VERSION;
+        
+        let mut writer: &mut [u8] = &mut data[..];
+        track.try_serialize(&mut writer)?;
+        
+        emit!(TrackMigratedEvent {
+            track: track_info.key(),
+            layout_version: TRACK_LAYOUT_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Регистрация слушателя (счетчик nonce и лимиты воспроизведений)
+    pub fn register_listener(ctx: Context<RegisterListener>) -> Result<()> {
+        let listener_state = &mut ctx.accounts.listener_state;
+        let listener = &ctx.accounts.listener;
+        
+        listener_state.listener = listener.key();
+        listener_state.last_nonce = 0;
+        listener_state.last_play_time = 0;
+        listener_state.window_start = 0;
+        listener_state.plays_in_window = 0;
+        listener_state.total_plays = 0;
+        listener_state.balance = 0;
+        listener_state.total_spent = 0;
+        
+        Ok(())
+    }
+
+    // Пополнение предоплаченного баланса слушателя в NDT
+    pub fn deposit_stream_balance(ctx: Context<DepositStreamBalance>, amount: u64) -> Result<()> {
+        let listener_state = &mut ctx.accounts.listener_state;
+        let listener = &ctx.accounts.listener;
+        
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.listener_token_account.to_account_info(),
+                    to: ctx.accounts.stream_vault.to_account_info(),
+                    authority: listener.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        listener_state.balance = listener_state.balance.checked_add(amount).unwrap();
+        
+        emit!(StreamBalanceDepositedEvent {
+            listener: listener.key(),
+            amount,
+            balance: listener_state.balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Вывод неиспользованного баланса слушателя
+    pub fn withdraw_stream_balance(ctx: Context<WithdrawStreamBalance>, amount: u64) -> Result<()> {
+        let listener_state = &mut ctx.accounts.listener_state;
+        let listener = &ctx.accounts.listener;
+        
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(listener_state.balance >= amount, ErrorCode::InsufficientStreamBalance);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stream_vault.to_account_info(),
+                    to: ctx.accounts.listener_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        listener_state.balance = listener_state.balance.checked_sub(amount).unwrap();
+        
+        emit!(StreamBalanceWithdrawnEvent {
+            listener: listener.key(),
+            amount,
+            balance: listener_state.balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Воспроизведение трека, подтвержденное подписью оракула.
+    // Предыдущая инструкция в транзакции должна быть ed25519-проверкой
+    // подписи play_oracle над (track, listener, nonce).
+    pub fn play_track(ctx: Context<PlayTrack>, track_id: Pubkey, nonce: u64) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let track = &mut ctx.accounts.track;
+        let listener_state = &mut ctx.accounts.listener_state;
+        let listener = &ctx.accounts.listener;
+        
+        require_keys_eq!(track.key(), track_id, ErrorCode::InvalidTrackAccount);
+        require!(track.is_listed, ErrorCode::TrackNotListed);
+        require!(track_nft.play_oracle != Pubkey::default(), ErrorCode::PlayOracleNotSet);
+        
+        // Защита от повторного использования аттестации
+        require!(nonce > listener_state.last_nonce, ErrorCode::NonceAlreadyUsed);
+        
+        let message = play_attestation_message(&track.key(), &listener.key(), nonce);
+        verify_ed25519_attestation(
+            &ctx.accounts.instructions.to_account_info(),
+            &track_nft.play_oracle,
+            &message,
+        )?;
+        
+        // Лимиты частоты воспроизведений
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time.checked_sub(listener_state.last_play_time).unwrap() >= track_nft.min_play_interval,
+            ErrorCode::PlayRateLimited
+        );
+        if current_time.checked_sub(listener_state.window_start).unwrap() >= PLAY_WINDOW_SECONDS {
+            listener_state.window_start = current_time;
+            listener_state.plays_in_window = 0;
+        }
+        require!(
+            listener_state.plays_in_window < track_nft.max_daily_plays,
+            ErrorCode::PlayRateLimited
+        );
+        
+        listener_state.last_nonce = nonce;
+        listener_state.last_play_time = current_time;
+        listener_state.plays_in_window = listener_state.plays_in_window.checked_add(1).unwrap();
+        listener_state.total_plays = listener_state.total_plays.checked_add(1).unwrap();
+        
+        // Активная подписка открывает весь каталог без оплаты за прослушивание
+        let has_subscription = match &ctx.accounts.subscription {
+            Some(subscription) => subscription.expires_at > current_time,
+            None => false,
+        };
+        
+        // Оплата за прослушивание из предоплаченного баланса
+        let stream_price = if has_subscription { 0 } else { track.stream_price };
+        if stream_price > 0 {
+            let creator_royalty = ctx
+                .accounts
+                .creator_royalty
+                .as_mut()
+                .ok_or(ErrorCode::MissingRoyaltyLedger)?;
+            require!(creator_royalty.creator == track.creator, ErrorCode::InvalidRoyaltyAccount);
+            require!(listener_state.balance >= stream_price, ErrorCode::InsufficientStreamBalance);
+            
+            let platform_fee = calculate_bps(stream_price, track_nft.platform_fee_bps);
+            let mut creator_amount = stream_price.checked_sub(platform_fee).unwrap();
+            
+            // Доля держателей франшизы переводится из хранилища балансов
+            if track.has_franchise {
+                let franchise = ctx
+                    .accounts
+                    .franchise
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingFranchiseAccounts)?;
+                let franchise_vault = ctx
+                    .accounts
+                    .franchise_vault
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingFranchiseAccounts)?;
+                let stream_vault = ctx
+                    .accounts
+                    .stream_vault
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingFranchiseAccounts)?;
+                let token_program = ctx
+                    .accounts
+                    .token_program
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingFranchiseAccounts)?;
+                
+                let franchise_cut = calculate_bps(creator_amount, franchise.revenue_share_bps);
+                if franchise_cut > 0 {
+                    let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+                    let signer = &[&seeds[..]];
+                    
+                    anchor_spl::token::transfer(
+                        CpiContext::new_with_signer(
+                            token_program.to_account_info(),
+                            anchor_spl::token::Transfer {
+                                from: stream_vault.to_account_info(),
+                                to: franchise_vault.to_account_info(),
+                                authority: track_nft.to_account_info(),
+                            },
+                            signer,
+                        ),
+                        franchise_cut,
+                    )?;
+                    franchise.accrue(franchise_cut);
+                    creator_amount = creator_amount.checked_sub(franchise_cut).unwrap();
+                }
+            }
+            
+            listener_state.balance = listener_state.balance.checked_sub(stream_price).unwrap();
+            listener_state.total_spent = listener_state.total_spent.checked_add(stream_price).unwrap();
+            creator_royalty.unclaimed = creator_royalty.unclaimed.checked_add(creator_amount).unwrap();
+            creator_royalty.total_earned = creator_royalty.total_earned.checked_add(creator_amount).unwrap();
+            track_nft.pending_stream_fees = track_nft.pending_stream_fees.checked_add(platform_fee).unwrap();
+            track.total_royalties_paid = track.total_royalties_paid.checked_add(creator_amount).unwrap();
+        }
+        
+        // Обновляем счетчик воспроизведений
+        track.play_count = track.play_count.checked_add(1).unwrap();
+        
+        emit!(TrackPlayedEvent {
+            track: track.key(),
+            listener: listener.key(),
+            nonce,
+            amount_paid: stream_price,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Пакетное начисление воспроизведений за эпоху.
+    // Треки передаются через remaining_accounts в том же порядке, что и `tallies`;
+    // вместе с периодом подписки за каждым треком следует его PDA прослушиваний
+    // за период [b"period_plays", period, track].
+    // Пакетные прослушивания только учитываются: stream_price с балансов
+    // слушателей списывает лишь play_track, агрегатор балансов не трогает.
+    pub fn settle_plays<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettlePlays<'info>>,
+        epoch: u64,
+        batch_index: u16,
+        merkle_root: [u8; 32],
+        tallies: Vec<PlayTally>,
+    ) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let play_epoch = &mut ctx.accounts.play_epoch;
+        let mut subscription_period = ctx.accounts.subscription_period.as_mut();
+        let payer = ctx.accounts.play_oracle.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        
+        let stride = if subscription_period.is_some() { 2 } else { 1 };
+        require!(!tallies.is_empty() && tallies.len() <= MAX_SETTLEMENT_BATCH, ErrorCode::InvalidSettlementBatch);
+        require!(
+            ctx.remaining_accounts.len() == tallies.len() * stride,
+            ErrorCode::InvalidSettlementBatch
+        );
+        
+        // Эпоха должна быть завершена
+        let current_time = Clock::get()?.unix_timestamp;
+        let epoch_end = (epoch as i64)
+            .checked_add(1)
+            .unwrap()
+            .checked_mul(PLAY_EPOCH_SECONDS)
+            .unwrap();
+        require!(epoch_end <= current_time, ErrorCode::EpochNotFinished);
+        
+        // Прослушивания эпохи учитываются в периоде подписки, если он открыт
+        let period = subscription_period_for_epoch(epoch);
+        if let Some(subscription_period) = subscription_period.as_ref() {
+            require!(subscription_period.period == period, ErrorCode::InvalidSubscriptionPeriod);
+            require!(!subscription_period.finalized, ErrorCode::SubscriptionPeriodFinalized);
+        }
+        
+        let week = chart_week_for_epoch(epoch);
+        let mut leaderboard = match ctx.accounts.leaderboard.as_ref() {
+            Some(leaderboard) => Some(leaderboard.load_mut()?),
+            None => None,
+        };
+        
+        let mut total_plays: u64 = 0;
+        for (tally, accounts) in tallies.iter().zip(ctx.remaining_accounts.chunks(stride)) {
+            let track_info = &accounts[0];
+            require!(track_info.key() == tally.track, ErrorCode::InvalidSettlementBatch);
+            require!(tally.count > 0, ErrorCode::InvalidSettlementBatch);
+            
+            let mut track: Account<'info, Track> = Account::try_from(track_info)?;
+            // Эпохи прошедших периодов затерли бы счетчики текущего
+            require!(period >= track.stats_period, ErrorCode::StalePlayPeriod);
+            track.play_count = track.play_count.checked_add(tally.count).unwrap();
+            
+            if subscription_period.is_some() {
+                record_period_plays(
+                    &accounts[1],
+                    period,
+                    &track.key(),
+                    tally.count,
+                    &payer,
+                    &system_program,
+                )?;
+            }
+            
+            // Переход к новому периоду: текущие прослушивания становятся предыдущими
+            if track.stats_period != period {
+                track.prev_period_plays = if track.stats_period.checked_add(1) == Some(period) {
+                    track.period_plays
+                } else {
+                    0
+                };
+                track.period_plays = 0;
+                track.stats_period = period;
+            }
+            track.period_plays = track.period_plays.checked_add(tally.count).unwrap();
+            
+            if track.record_chart_activity(week, tally.count, 0) {
+                if let Some(leaderboard) = leaderboard.as_mut() {
+                    leaderboard.record(week, track.key(), track.week_plays, track.week_sales);
+                }
+            }
+            track.exit(&crate::ID)?;
+            
+            total_plays = total_plays.checked_add(tally.count).unwrap();
+        }
+        
+        play_epoch.epoch = epoch;
+        play_epoch.batch_index = batch_index;
+        play_epoch.merkle_root = merkle_root;
+        play_epoch.total_plays = total_plays;
+        play_epoch.track_count = tallies.len() as u16;
+        play_epoch.settled_at = current_time;
+        
+        if let Some(subscription_period) = subscription_period.as_mut() {
+            subscription_period.total_plays = subscription_period.total_plays.checked_add(total_plays).unwrap();
+        }
+        
+        if epoch > track_nft.last_settled_epoch {
+            track_nft.last_settled_epoch = epoch;
+        }
+        
+        emit!(PlaysSettledEvent {
+            epoch,
+            batch_index,
+            merkle_root,
+            total_plays,
+            track_count: tallies.len() as u16,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Создание таблицы лидеров недельного чарта
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.track_nft.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        let mut leaderboard = ctx.accounts.leaderboard.load_init()?;
+        leaderboard.week = chart_week_at(Clock::get()?.unix_timestamp);
+        leaderboard.entry_count = 0;
+        
+        Ok(())
+    }
+
+    // Закрытие недели чарта: отсортированный снимок для наград, таблица
+    // начинает текущую неделю с нуля. Доступно всем после недели и запаса на досчет.
+    pub fn rollover_chart(ctx: Context<RolloverChart>, week: u64) -> Result<()> {
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        let mut snapshot = ctx.accounts.chart_snapshot.load_init()?;
+        
+        require!(leaderboard.week == week, ErrorCode::InvalidChartWeek);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        let week_end = (week as i64)
+            .checked_add(1)
+            .unwrap()
+            .checked_mul(CHART_WEEK_SECONDS)
+            .unwrap();
+        require!(
+            current_time >= week_end.checked_add(CHART_SETTLEMENT_GRACE).unwrap(),
+            ErrorCode::ChartWeekNotFinished
+        );
+        
+        let count = leaderboard.entry_count as usize;
+        leaderboard.entries[..count].sort_unstable_by_key(|e| std::cmp::Reverse(e.score));
+        
+        snapshot.week = week;
+        snapshot.entry_count = leaderboard.entry_count;
+        snapshot.finalized_at = current_time;
+        snapshot.entries[..count].copy_from_slice(&leaderboard.entries[..count]);
+        
+        // Треки вернутся в таблицу со своими недельными счетчиками при следующей активности
+        leaderboard.week = chart_week_at(current_time);
+        leaderboard.entry_count = 0;
+        
+        emit!(ChartRolledOverEvent {
+            week,
+            entry_count: snapshot.entry_count,
+            top_track: if count > 0 { snapshot.entries[0].track } else { Pubkey::default() },
+            next_week: leaderboard.week,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Настройка недельных наград чарта и создание их хранилища
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_chart_rewards(
+        ctx: Context<InitializeChartRewards>,
+        curve: RewardCurve,
+        decay_bps: u16,
+        ranks_paid: u8,
+        weekly_budget: u64,
+        min_play_count: u64,
+        min_like_count: u64,
+    ) -> Result<()> {
+        let reward_config = &mut ctx.accounts.reward_config;
+        
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.track_nft.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        reward_config.ndt_mint = ctx.accounts.ndt_mint.key();
+        reward_config.vault_bump = ctx.bumps.reward_vault;
+        reward_config.bump = ctx.bumps.reward_config;
+        reward_config.outstanding = 0;
+        reward_config.total_paid = 0;
+        reward_config.apply(curve, decay_bps, ranks_paid, weekly_budget, min_play_count, min_like_count)?;
+        
+        emit!(ChartRewardParamsUpdatedEvent {
+            curve,
+            decay_bps,
+            ranks_paid,
+            weekly_budget,
+            min_play_count,
+            min_like_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Изменение кривой и бюджета; открытые пулы сохраняют свои параметры
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_chart_reward_params(
+        ctx: Context<SetChartRewardParams>,
+        curve: RewardCurve,
+        decay_bps: u16,
+        ranks_paid: u8,
+        weekly_budget: u64,
+        min_play_count: u64,
+        min_like_count: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.track_nft.authority,
+            ErrorCode::Unauthorized
+        );
+        
+        ctx.accounts
+            .reward_config
+            .apply(curve, decay_bps, ranks_paid, weekly_budget, min_play_count, min_like_count)?;
+        
+        emit!(ChartRewardParamsUpdatedEvent {
+            curve,
+            decay_bps,
+            ranks_paid,
+            weekly_budget,
+            min_play_count,
+            min_like_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Пополнение хранилища наград (обычно authority NDT; доля комиссий
+    // переводов NDT поступает в хранилище напрямую)
+    pub fn fund_chart_rewards(ctx: Context<FundChartRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.funder_token_account.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        emit!(ChartRewardsFundedEvent {
+            funder: ctx.accounts.funder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Открытие пула недели по снимку чарта: свободный остаток хранилища
+    // в пределах недельного бюджета делится между местами по кривой
+    pub fn open_chart_rewards(ctx: Context<OpenChartRewards>, week: u64) -> Result<()> {
+        let reward_config = &mut ctx.accounts.reward_config;
+        let reward_pool = &mut ctx.accounts.reward_pool;
+        let snapshot = ctx.accounts.chart_snapshot.load()?;
+        
+        let available = ctx
+            .accounts
+            .reward_vault
+            .amount
+            .saturating_sub(reward_config.outstanding);
+        let total = reward_config.weekly_budget.min(available);
+        let ranks_paid = (reward_config.ranks_paid as u64).min(snapshot.entry_count) as u8;
+        require!(total > 0 && ranks_paid > 0, ErrorCode::NoChartRewards);
+        
+        let total_weight: u128 = (0..ranks_paid)
+            .map(|rank| chart_reward_weight(reward_config.curve, reward_config.decay_bps, ranks_paid, rank))
+            .sum();
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        reward_pool.week = week;
+        reward_pool.total = total;
+        reward_pool.claimed = 0;
+        reward_pool.curve = reward_config.curve;
+        reward_pool.decay_bps = reward_config.decay_bps;
+        reward_pool.ranks_paid = ranks_paid;
+        reward_pool.total_weight = total_weight;
+        reward_pool.min_play_count = reward_config.min_play_count;
+        reward_pool.min_like_count = reward_config.min_like_count;
+        reward_pool.claimed_ranks = 0;
+        reward_pool.opened_at = current_time;
+        reward_pool.opened_by = ctx.accounts.payer.key();
+        
+        reward_config.outstanding = reward_config.outstanding.checked_add(total).unwrap();
+        
+        emit!(ChartRewardsOpenedEvent {
+            week,
+            total,
+            ranks_paid,
+            curve: reward_pool.curve,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Получение награды за место в чарте недели. Выплата идет на payout_wallet
+    // трека; трек должен набрать минимум прослушиваний и лайков за все время.
+    pub fn claim_chart_reward(ctx: Context<ClaimChartReward>, week: u64, rank: u8) -> Result<()> {
+        let reward_config = &mut ctx.accounts.reward_config;
+        let reward_pool = &mut ctx.accounts.reward_pool;
+        let track = &ctx.accounts.track;
+        let snapshot = ctx.accounts.chart_snapshot.load()?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time < reward_pool.opened_at.checked_add(CHART_CLAIM_WINDOW).unwrap(),
+            ErrorCode::ChartRewardsExpired
+        );
+        require!(!track.taken_down, ErrorCode::TrackTakenDown);
+        require!(track.is_listed, ErrorCode::TrackNotListed);
+        require!(!track.under_dispute, ErrorCode::TrackUnderDispute);
+        require!(rank < reward_pool.ranks_paid, ErrorCode::InvalidChartRank);
+        require!(
+            snapshot.entries[rank as usize].track == track.key(),
+            ErrorCode::InvalidChartRank
+        );
+        require!(
+            reward_pool.claimed_ranks & (1u64 << rank) == 0,
+            ErrorCode::ChartRewardClaimed
+        );
+        require!(
+            track.play_count >= reward_pool.min_play_count && track.like_count >= reward_pool.min_like_count,
+            ErrorCode::TrackNotEligible
+        );
+        
+        let amount = reward_pool.reward_for(rank);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.payout_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        reward_pool.claimed_ranks |= 1u64 << rank;
+        reward_pool.claimed = reward_pool.claimed.checked_add(amount).unwrap();
+        reward_config.outstanding = reward_config.outstanding.saturating_sub(amount);
+        reward_config.total_paid = reward_config.total_paid.checked_add(amount).unwrap();
+        
+        emit!(ChartRewardClaimedEvent {
+            week,
+            rank,
+            track: track.key(),
+            creator: track.creator,
+            payout_wallet: track.payout_wallet,
+            amount,
+            play_count: track.play_count,
+            like_count: track.like_count,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Закрытие пула после срока получения: невостребованный остаток
+    // возвращается в свободный баланс хранилища
+    pub fn close_chart_rewards(ctx: Context<CloseChartRewards>, week: u64) -> Result<()> {
+        let reward_config = &mut ctx.accounts.reward_config;
+        let reward_pool = &ctx.accounts.reward_pool;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= reward_pool.opened_at.checked_add(CHART_CLAIM_WINDOW).unwrap(),
+            ErrorCode::ChartRewardsActive
+        );
+        
+        let unclaimed = reward_pool.total.saturating_sub(reward_pool.claimed);
+        reward_config.outstanding = reward_config.outstanding.saturating_sub(unclaimed);
+        
+        emit!(ChartRewardsClosedEvent {
+            week,
+            unclaimed,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Создание тарифного плана подписки
+    pub fn create_subscription_plan(
+        ctx: Context<CreateSubscriptionPlan>,
+        plan_id: u16,
+        price: u64,
+        duration: i64,
+    ) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
+        let plan = &mut ctx.accounts.plan;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(
+            price > 0 && duration > 0 && duration <= MAX_PLAN_PERIODS * SUBSCRIPTION_PERIOD_SECONDS,
+            ErrorCode::InvalidSubscriptionPlan
+        );
+        
+        plan.plan_id = plan_id;
+        plan.price = price;
+        plan.duration = duration;
+        plan.is_active = true;
+        
+        emit!(SubscriptionPlanUpdatedEvent {
+            plan: plan.key(),
+            price,
+            duration,
+            is_active: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Изменение тарифного плана
+    pub fn update_subscription_plan(
+        ctx: Context<UpdateSubscriptionPlan>,
+        price: u64,
+        duration: i64,
+        is_active: bool,
+    ) -> Result<()> {
+        let track_nft = &ctx.accounts.track_nft;
+        let plan = &mut ctx.accounts.plan;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track_nft.authority, ErrorCode::Unauthorized);
+        require!(
+            price > 0 && duration > 0 && duration <= MAX_PLAN_PERIODS * SUBSCRIPTION_PERIOD_SECONDS,
+            ErrorCode::InvalidSubscriptionPlan
+        );
+        
+        plan.price = price;
+        plan.duration = duration;
+        plan.is_active = is_active;
+        
+        emit!(SubscriptionPlanUpdatedEvent {
+            plan: plan.key(),
+            price,
+            duration,
+            is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Открытие текущего периода подписки (пул и счетчик прослушиваний).
+    // Пул получает доли, отложенные для периода оплатами прошлых периодов.
+    pub fn open_subscription_period(ctx: Context<OpenSubscriptionPeriod>, period: u64) -> Result<()> {
+        let subscription_period = &mut ctx.accounts.subscription_period;
+        let schedule = &mut ctx.accounts.subscription_schedule;
+        
+        let current_period = (Clock::get()?.unix_timestamp / SUBSCRIPTION_PERIOD_SECONDS) as u64;
+        require!(period == current_period, ErrorCode::InvalidSubscriptionPeriod);
+        
+        let slot = (period % SUBSCRIPTION_SCHEDULE_PERIODS as u64) as usize;
+        let deferred = schedule.amounts[slot];
+        schedule.amounts[slot] = 0;
+        schedule.deferred_total = schedule.deferred_total.checked_sub(deferred).unwrap();
+        
+        subscription_period.period = period;
+        subscription_period.pool_amount = deferred;
+        subscription_period.total_plays = 0;
+        subscription_period.distributed_amount = 0;
+        subscription_period.finalized = false;
+        
+        Ok(())
+    }
+
+    // Оформление подписки
+    pub fn subscribe(ctx: Context<Subscribe>) -> Result<()> {
+        let plan = &ctx.accounts.plan;
+        let subscriber = &ctx.accounts.subscriber;
+        
+        require!(plan.is_active, ErrorCode::SubscriptionPlanInactive);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        pay_subscription(
+            &mut ctx.accounts.track_nft,
+            &mut ctx.accounts.subscription_period,
+            &mut ctx.accounts.subscription_schedule,
+            &ctx.accounts.token_program,
+            &ctx.accounts.subscriber_token_account,
+            &ctx.accounts.stream_vault,
+            subscriber,
+            plan,
+            current_time,
+            current_time,
+        )?;
+        
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.subscriber = subscriber.key();
+        subscription.plan = plan.key();
+        subscription.started_at = current_time;
+        subscription.expires_at = current_time.checked_add(plan.duration).unwrap();
+        subscription.cancelled = false;
+        subscription.total_paid = plan.price;
+        
+        emit!(SubscribedEvent {
+            subscriber: subscriber.key(),
+            plan: plan.key(),
+            amount: plan.price,
+            expires_at: subscription.expires_at,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Продление подписки (срок добавляется к текущему окончанию)
+    pub fn renew_subscription(ctx: Context<RenewSubscription>) -> Result<()> {
+        let plan = &ctx.accounts.plan;
+        let subscriber = &ctx.accounts.subscriber;
+        
+        require!(plan.is_active, ErrorCode::SubscriptionPlanInactive);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        let base = ctx.accounts.subscription.expires_at.max(current_time);
+        pay_subscription(
+            &mut ctx.accounts.track_nft,
+            &mut ctx.accounts.subscription_period,
+            &mut ctx.accounts.subscription_schedule,
+            &ctx.accounts.token_program,
+            &ctx.accounts.subscriber_token_account,
+            &ctx.accounts.stream_vault,
+            subscriber,
+            plan,
+            base,
+            current_time,
+        )?;
+        
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.plan = plan.key();
+        subscription.expires_at = base.checked_add(plan.duration).unwrap();
+        subscription.cancelled = false;
+        subscription.total_paid = subscription.total_paid.checked_add(plan.price).unwrap();
+        
+        emit!(SubscribedEvent {
+            subscriber: subscriber.key(),
+            plan: plan.key(),
+            amount: plan.price,
+            expires_at: subscription.expires_at,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Отмена подписки: доступ сохраняется до конца оплаченного срока
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        
+        require!(!subscription.cancelled, ErrorCode::SubscriptionCancelled);
+        subscription.cancelled = true;
+        
+        emit!(SubscriptionCancelledEvent {
+            subscriber: subscription.subscriber,
+            expires_at: subscription.expires_at,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Закрытие периода оракулом после начисления всех прослушиваний
+    pub fn finalize_subscription_period(ctx: Context<FinalizeSubscriptionPeriod>) -> Result<()> {
+        let subscription_period = &mut ctx.accounts.subscription_period;
+        
+        let period_end = (subscription_period.period as i64)
+            .checked_add(1)
+            .unwrap()
+            .checked_mul(SUBSCRIPTION_PERIOD_SECONDS)
+            .unwrap();
+        require!(Clock::get()?.unix_timestamp >= period_end, ErrorCode::SubscriptionPeriodNotFinished);
+        require!(!subscription_period.finalized, ErrorCode::SubscriptionPeriodFinalized);
+        
+        subscription_period.finalized = true;
+        
+        emit!(SubscriptionPeriodFinalizedEvent {
+            period: subscription_period.period,
+            pool_amount: subscription_period.pool_amount,
+            total_plays: subscription_period.total_plays,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Начисление доли пула подписок создателю трека (пропорционально прослушиваниям)
+    pub fn distribute_subscription_share(ctx: Context<DistributeSubscriptionShare>) -> Result<()> {
+        let subscription_period = &mut ctx.accounts.subscription_period;
+        let track = &ctx.accounts.track;
+        let creator_royalty = &mut ctx.accounts.creator_royalty;
+        let claim = &mut ctx.accounts.claim;
+        
+        require!(subscription_period.finalized, ErrorCode::SubscriptionPeriodNotFinished);
+        
+        let period = subscription_period.period;
+        let track_plays = ctx.accounts.period_plays.plays;
+        require!(track_plays > 0 && subscription_period.total_plays > 0, ErrorCode::NoPlaysInPeriod);
+        
+        let share = (subscription_period.pool_amount as u128)
+            .checked_mul(track_plays as u128)
+            .unwrap()
+            .checked_div(subscription_period.total_plays as u128)
+            .unwrap() as u64;
+        
+        subscription_period.distributed_amount = subscription_period.distributed_amount.checked_add(share).unwrap();
+        creator_royalty.unclaimed = creator_royalty.unclaimed.checked_add(share).unwrap();
+        creator_royalty.total_earned = creator_royalty.total_earned.checked_add(share).unwrap();
+        
+        claim.period = period;
+        claim.track = track.key();
+        claim.plays = track_plays;
+        claim.amount = share;
+        
+        emit!(SubscriptionShareDistributedEvent {
+            period,
+            track: track.key(),
+            creator: track.creator,
+            plays: track_plays,
+            amount: share,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Проверка, что воспроизведение слушателя вошло в начисленный пакет
+    pub fn verify_play_receipt(
+        ctx: Context<VerifyPlayReceipt>,
+        listener: Pubkey,
+        track: Pubkey,
+        nonce: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let play_epoch = &ctx.accounts.play_epoch;
+        
+        let leaf = play_receipt_leaf(&listener, &track, play_epoch.epoch, nonce);
+        require!(
+            verify_merkle_proof(&proof, play_epoch.merkle_root, leaf),
+            ErrorCode::InvalidPlayProof
+        );
+        
+        Ok(())
+    }
+
+    // Лайк трека
+    // Один лайк на кошелек: PDA лайка создается один раз на (track, listener)
+    pub fn like_track(ctx: Context<LikeTrack>, track_id: Pubkey) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let like = &mut ctx.accounts.like;
+        let listener = &ctx.accounts.listener;
+        
+        require_keys_eq!(track.key(), track_id, ErrorCode::InvalidTrackAccount);
+        require!(track.is_listed, ErrorCode::TrackNotListed);
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        like.track = track.key();
+        like.listener = listener.key();
+        like.liked_at = current_time;
+        
+        // Обновляем счетчик лайков
+        track.like_count = track.like_count.checked_add(1).unwrap();
+        
+        emit!(TrackLikedEvent {
+            track: track.key(),
+            listener: listener.key(),
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Снятие лайка (PDA лайка закрывается, рента возвращается слушателю)
+    pub fn unlike_track(ctx: Context<UnlikeTrack>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let listener = &ctx.accounts.listener;
+        
+        track.like_count = track.like_count.saturating_sub(1);
+        
+        emit!(TrackUnlikedEvent {
+            track: track.key(),
+            listener: listener.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Установка цены за одно прослушивание (списывается в play_track;
+    // прослушивания через settle_plays бесплатны для слушателя)
+    pub fn set_stream_price(ctx: Context<SetStreamPrice>, stream_price: u64) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        
+        track.stream_price = stream_price;
+        
+        emit!(StreamPriceUpdatedEvent {
+            track: track.key(),
+            stream_price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Открытие роялти-счета создателя для поточных выплат
+    pub fn init_royalty_account(ctx: Context<InitRoyaltyAccount>) -> Result<()> {
+        let royalty_account = &mut ctx.accounts.royalty_account;
+        
+        royalty_account.creator = ctx.accounts.creator.key();
+        royalty_account.total_earned = 0;
+        royalty_account.last_claim_time = 0;
+        royalty_account.unclaimed = 0;
+        royalty_account.total_tips = 0;
+        royalty_account.tip_count = 0;
+        royalty_account.beneficiary = ctx.accounts.creator.key();
+        royalty_account.has_split_sheet = false;
+        
+        Ok(())
+    }
+
+    // Создание сплит-листа создателя
+    pub fn create_split_sheet(ctx: Context<CreateSplitSheet>, recipients: Vec<SplitRecipient>) -> Result<()> {
+        validate_split_recipients(&recipients)?;
+        
+        let split_sheet = &mut ctx.accounts.split_sheet;
+        split_sheet.creator = ctx.accounts.creator.key();
+        split_sheet.recipients = recipients;
+        
+        // С этого момента чаевые обязаны идти через сплит-лист
+        ctx.accounts.royalty_account.has_split_sheet = true;
+        
+        emit!(SplitSheetUpdatedEvent {
+            creator: split_sheet.creator,
+            recipient_count: split_sheet.recipients.len() as u8,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Изменение долей в сплит-листе
+    pub fn update_split_sheet(ctx: Context<UpdateSplitSheet>, recipients: Vec<SplitRecipient>) -> Result<()> {
+        validate_split_recipients(&recipients)?;
+        
+        let split_sheet = &mut ctx.accounts.split_sheet;
+        split_sheet.recipients = recipients;
+        
+        emit!(SplitSheetUpdatedEvent {
+            creator: split_sheet.creator,
+            recipient_count: split_sheet.recipients.len() as u8,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Создание профиля артиста
+    pub fn create_artist_profile(
+        ctx: Context<CreateArtistProfile>,
+        display_name: String,
+        avatar_cid: String,
+        socials_hash: [u8; 32],
+        default_royalty: u8,
+        split_template: Vec<SplitRecipient>,
+    ) -> Result<()> {
+        let artist_profile = &mut ctx.accounts.artist_profile;
+        let creator = &ctx.accounts.creator;
+        
+        require!(
+            !display_name.is_empty() && display_name.len() <= MAX_DISPLAY_NAME_LEN,
+            ErrorCode::InvalidArtistProfile
+        );
+        require!(avatar_cid.len() <= MAX_AVATAR_CID_LEN, ErrorCode::InvalidArtistProfile);
+        require!(default_royalty <= 50, ErrorCode::RoyaltyTooHigh);
+        validate_split_recipients(&split_template)?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        
+        artist_profile.creator = creator.key();
+        artist_profile.display_name = display_name;
+        artist_profile.avatar_cid = avatar_cid;
+        artist_profile.socials_hash = socials_hash;
+        artist_profile.status = VerificationStatus::Unverified;
+        artist_profile.payout_wallet = ctx.accounts.payout_token_account.owner;
+        artist_profile.default_royalty = default_royalty;
+        artist_profile.split_template = split_template;
+        artist_profile.track_count = 0;
+        artist_profile.created_at = current_time;
+        artist_profile.verified_at = 0;
+        artist_profile.bump = ctx.bumps.artist_profile;
+        
+        emit!(ArtistProfileUpdatedEvent {
+            creator: creator.key(),
+            display_name: artist_profile.display_name.clone(),
+            payout_wallet: artist_profile.payout_wallet,
+            default_royalty,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Изменение профиля. Смена имени или соцсетей снимает верификацию.
+    pub fn update_artist_profile(
+        ctx: Context<UpdateArtistProfile>,
+        display_name: Option<String>,
+        avatar_cid: Option<String>,
+        socials_hash: Option<[u8; 32]>,
+        default_royalty: Option<u8>,
+        split_template: Option<Vec<SplitRecipient>>,
+    ) -> Result<()> {
+        let artist_profile = &mut ctx.accounts.artist_profile;
+        
+        let mut identity_changed = false;
+        if let Some(display_name) = display_name {
+            require!(
+                !display_name.is_empty() && display_name.len() <= MAX_DISPLAY_NAME_LEN,
+                ErrorCode::InvalidArtistProfile
+            );
+            identity_changed |= display_name != artist_profile.display_name;
+            artist_profile.display_name = display_name;
+        }
+        if let Some(avatar_cid) = avatar_cid {
+            require!(avatar_cid.len() <= MAX_AVATAR_CID_LEN, ErrorCode::InvalidArtistProfile);
+            artist_profile.avatar_cid = avatar_cid;
+        }
+        if let Some(socials_hash) = socials_hash {
+            identity_changed |= socials_hash != artist_profile.socials_hash;
+            artist_profile.socials_hash = socials_hash;
+        }
+        if let Some(default_royalty) = default_royalty {
+            require!(default_royalty <= 50, ErrorCode::RoyaltyTooHigh);
+            artist_profile.default_royalty = default_royalty;
+        }
+        if let Some(split_template) = split_template {
+            validate_split_recipients(&split_template)?;
+            artist_profile.split_template = split_template;
+        }
+        // Новые треки будут выплачивать на этот аккаунт; выпущенные - через redirect_track_payout
+        if let Some(payout_token_account) = &ctx.accounts.payout_token_account {
+            artist_profile.payout_wallet = payout_token_account.owner;
+        }
+        
+        if identity_changed && artist_profile.status == VerificationStatus::Verified {
+            artist_profile.status = VerificationStatus::Unverified;
+            artist_profile.verified_at = 0;
+        }
+        
+        emit!(ArtistProfileUpdatedEvent {
+            creator: artist_profile.creator,
+            display_name: artist_profile.display_name.clone(),
+            payout_wallet: artist_profile.payout_wallet,
+            default_royalty: artist_profile.default_royalty,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Верификация или блокировка профиля платформой
+    pub fn set_profile_verification(
+        ctx: Context<SetProfileVerification>,
+        status: VerificationStatus,
+    ) -> Result<()> {
+        let artist_profile = &mut ctx.accounts.artist_profile;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == ctx.accounts.track_nft.authority, ErrorCode::Unauthorized);
+        
+        // Верифицированное имя закрепляется за профилем
+        if status == VerificationStatus::Verified {
+            let name_claim = ctx
+                .accounts
+                .name_claim
+                .as_mut()
+                .ok_or(ErrorCode::MissingArtistNameClaim)?;
+            require!(
+                name_claim.creator == Pubkey::default() || name_claim.creator == artist_profile.creator,
+                ErrorCode::ArtistNameTaken
+            );
+            name_claim.creator = artist_profile.creator;
+            name_claim.bump = ctx.bumps.name_claim.unwrap();
+        }
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        artist_profile.status = status;
+        artist_profile.verified_at = if status == VerificationStatus::Verified {
+            current_time
+        } else {
+            0
+        };
+        
+        emit!(ArtistVerificationEvent {
+            creator: artist_profile.creator,
+            status,
+            verifier: authority.key(),
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Перенос шаблона долей из профиля в сплит-лист артиста
+    pub fn apply_split_template(ctx: Context<ApplySplitTemplate>) -> Result<()> {
+        let split_sheet = &mut ctx.accounts.split_sheet;
+        split_sheet.creator = ctx.accounts.creator.key();
+        split_sheet.recipients = ctx.accounts.artist_profile.split_template.clone();
+        ctx.accounts.royalty_account.has_split_sheet = true;
+        
+        emit!(SplitSheetUpdatedEvent {
+            creator: split_sheet.creator,
+            recipient_count: split_sheet.recipients.len() as u8,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Чаевые артисту. Если у артиста есть сплит-лист, remaining_accounts -
+    // токен-аккаунты получателей в порядке сплит-листа; остаток получает артист.
+    pub fn tip_artist<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TipArtist<'info>>,
+        amount: u64,
+        message_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let track_nft = &mut ctx.accounts.track_nft;
+        let royalty_account = &mut ctx.accounts.royalty_account;
+        let tipper = &ctx.accounts.tipper;
+        let artist = royalty_account.creator;
+        
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(tipper.key() != artist, ErrorCode::CreatorCannotBuy);
+        require!(
+            !royalty_account.has_split_sheet || ctx.accounts.split_sheet.is_some(),
+            ErrorCode::MissingSplitSheet
+        );
+        
+        let platform_fee = calculate_bps(amount, track_nft.tip_fee_bps);
+        let net_amount = amount.checked_sub(platform_fee).unwrap();
+        
+        // Доли соавторов по сплит-листу
+        let mut distributed: u64 = 0;
+        if let Some(split_sheet) = &ctx.accounts.split_sheet {
+            require!(
+                ctx.remaining_accounts.len() == split_sheet.recipients.len(),
+                ErrorCode::InvalidSplitAccounts
+            );
+            for (recipient, recipient_info) in split_sheet.recipients.iter().zip(ctx.remaining_accounts.iter()) {
+                let recipient_account: Account<'info, TokenAccount> = Account::try_from(recipient_info)?;
+                require!(recipient_account.owner == recipient.wallet, ErrorCode::InvalidSplitAccounts);
+                require!(recipient_account.mint == track_nft.payment_mint, ErrorCode::InvalidPaymentMint);
+                
+                let share = calculate_bps(net_amount, recipient.share_bps);
+                if share > 0 {
+                    anchor_spl::token::transfer(
+                        CpiContext::new(
+                            ctx.accounts.token_program.to_account_info(),
+                            anchor_spl::token::Transfer {
+                                from: ctx.accounts.tipper_token_account.to_account_info(),
+                                to: recipient_info.clone(),
+                                authority: tipper.to_account_info(),
+                            },
+                        ),
+                        share,
+                    )?;
+                    distributed = distributed.checked_add(share).unwrap();
+                }
+            }
+        }
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.tipper_token_account.to_account_info(),
+                    to: ctx.accounts.artist_token_account.to_account_info(),
+                    authority: tipper.to_account_info(),
+                },
+            ),
+            net_amount.checked_sub(distributed).unwrap(),
+        )?;
+        
+        if platform_fee > 0 {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: ctx.accounts.tipper_token_account.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: tipper.to_account_info(),
+                    },
+                ),
+                platform_fee,
+            )?;
+            track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(platform_fee).unwrap();
+        }
+        
+        // Статистика чаевых для лидербордов
+        royalty_account.total_tips = royalty_account.total_tips.checked_add(amount).unwrap();
+        royalty_account.tip_count = royalty_account.tip_count.checked_add(1).unwrap();
+        
+        emit!(TipEvent {
+            artist,
+            tipper: tipper.key(),
+            amount,
+            platform_fee,
+            message_hash,
+            total_tips: royalty_account.total_tips,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Вывод накопленных роялти за прослушивания.
+    // Подпись не требуется: средства уходят только на счет получателя.
+    pub fn claim_stream_royalties(ctx: Context<ClaimStreamRoyalties>) -> Result<()> {
+        let royalty_account = &mut ctx.accounts.royalty_account;
+        
+        let amount = royalty_account.unclaimed;
+        require!(amount > 0, ErrorCode::NoRoyaltiesToClaim);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stream_vault.to_account_info(),
+                    to: ctx.accounts.creator_token_account.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        let current_time = Clock::get()?.unix_timestamp;
+        royalty_account.unclaimed = 0;
+        royalty_account.last_claim_time = current_time;
+        
+        emit!(StreamRoyaltiesClaimedEvent {
+            creator: royalty_account.creator,
+            beneficiary: royalty_account.beneficiary,
+            amount,
+            timestamp: current_time,
+        });
+        
+        Ok(())
+    }
+
+    // Перевод накопленной комиссии за прослушивания в казну
+    pub fn sweep_stream_fees(ctx: Context<SweepStreamFees>) -> Result<()> {
+        let amount = ctx.accounts.track_nft.pending_stream_fees;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        
+        let seeds = &[b"tracknft".as_ref(), &[ctx.bumps.track_nft]];
+        let signer = &[&seeds[..]];
+        
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.stream_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.track_nft.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        
+        let track_nft = &mut ctx.accounts.track_nft;
+        track_nft.pending_stream_fees = 0;
+        track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(amount).unwrap();
+        
+        Ok(())
+    }
+
+    // Обновление цены трека
+    pub fn update_price(ctx: Context<UpdatePrice>, new_price: u64) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(!track.has_curve, ErrorCode::CurvePricedTrack);
+        // Аренда не может стоить дороже покупки
+        require!(
+            track.rental_duration == 0 || track.rental_price <= new_price,
+            ErrorCode::InvalidRentalTerms
+        );
+        
+        track.price = new_price;
+        
+        emit!(PriceUpdatedEvent {
+            track: track.key(),
+            new_price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Обновление роялти
+    pub fn update_royalty(ctx: Context<UpdateRoyalty>, new_royalty: u8) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(new_royalty <= 50, ErrorCode::RoyaltyTooHigh);
+        
+        track.royalty_percentage = new_royalty;
+        
+        emit!(RoyaltyUpdatedEvent {
+            track: track.key(),
+            new_royalty,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Перенаправление выплат трека в фонд мемориала ушедшего артиста.
+    // Вызывается программой мемориалов (CPI с подписью PDA верифицированного
+    // мемориала создателя); получатель - PDA мемориала, владелец его хранилища.
+    pub fn redirect_track_payout(ctx: Context<RedirectTrackPayout>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let memorial = &ctx.accounts.memorial;
+        
+        require_memorial_signer(&memorial.key(), &track.creator)?;
+        
+        track.payout_wallet = memorial.key();
+        
+        emit!(PayoutRedirectedEvent {
+            creator: track.creator,
+            target: track.key(),
+            payout_wallet: memorial.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Перенаправление поточных роялти и чаевых создателя в фонд мемориала
+    pub fn redirect_creator_royalties(ctx: Context<RedirectCreatorRoyalties>) -> Result<()> {
+        let royalty_account = &mut ctx.accounts.royalty_account;
+        let memorial = &ctx.accounts.memorial;
+        
+        require_memorial_signer(&memorial.key(), &royalty_account.creator)?;
+        
+        royalty_account.beneficiary = memorial.key();
+        
+        emit!(PayoutRedirectedEvent {
+            creator: royalty_account.creator,
+            target: royalty_account.key(),
+            payout_wallet: memorial.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Списание/снятие с продажи
+    pub fn toggle_listing(ctx: Context<ToggleListing>) -> Result<()> {
+        let track = &mut ctx.accounts.track;
+        let authority = &ctx.accounts.authority;
+        
+        require!(authority.key() == track.creator, ErrorCode::Unauthorized);
+        require!(!track.taken_down, ErrorCode::TrackTakenDown);
+        
+        track.is_listed = !track.is_listed;
+        
+        emit!(ListingToggledEvent {
+            track: track.key(),
+            is_listed: track.is_listed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Выплата роялти создателям
+    pub fn pay_royalties(ctx: Context<PayRoyalties>) -> Result<()> {
+        let royalty_pool = &mut ctx.accounts.royalty_pool;
+        
+        // Логика выплаты роялти из пула
+        // Здесь можно реализовать сложную логику распределения роялти
+        
+        emit!(RoyaltiesPaidEvent {
+            total_amount: royalty_pool.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+// Helper функции
+fn calculate_bps(amount: u64, bps: u16) -> u64 {
+    amount
+        .checked_mul(bps as u64)
+        .unwrap()
+        .checked_div(BPS_DENOMINATOR)
+        .unwrap()
+}
+
+// Выплата долей родительских треков ремикса.
+// parent_accounts: пары (родительский track, токен-аккаунт его payout_wallet).
+#[allow(clippy::too_many_arguments)]
+fn pay_upstream_royalties<'info>(
+    track: &Account<'info, Track>,
+    parent_accounts: &'info [AccountInfo<'info>],
+    payment_mint: &Pubkey,
+    from: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &AccountInfo<'info>,
+    artist_amount: u64,
+    current_time: i64,
+) -> Result<u64> {
+    require!(
+        parent_accounts.len() == track.upstream.len() * 2,
+        ErrorCode::InvalidRemixAccounts
+    );
+    
+    let mut total_paid: u64 = 0;
+    for (upstream, pair) in track.upstream.iter().zip(parent_accounts.chunks(2)) {
+        require!(pair[0].key() == upstream.parent_track, ErrorCode::InvalidRemixAccounts);
+        let parent: Account<'info, Track> = Account::try_from(&pair[0])?;
+        let parent_token_account: Account<'info, TokenAccount> = Account::try_from(&pair[1])?;
+        require!(
+            parent_token_account.owner == parent.payout_wallet,
+            ErrorCode::InvalidRemixAccounts
+        );
+        require!(parent_token_account.mint == *payment_mint, ErrorCode::InvalidPaymentMint);
+        
+        let upstream_cut = calculate_bps(artist_amount, upstream.share_bps);
+        if upstream_cut == 0 {
+            continue;
+        }
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: from.clone(),
+                    to: parent_token_account.to_account_info(),
+                    authority: authority.clone(),
+                },
+                signer,
+            ),
+            upstream_cut,
+        )?;
+        total_paid = total_paid.checked_add(upstream_cut).unwrap();
+        
+        emit!(UpstreamRoyaltyPaidEvent {
+            track: track.key(),
+            parent_track: upstream.parent_track,
+            recipient: parent.payout_wallet,
+            amount: upstream_cut,
+            timestamp: current_time,
+        });
+    }
+    
+    Ok(total_paid)
+}
+
+// Счета покупки, общие для buy_track и gift_track
+struct Purchase<'a, 'info> {
+    track_nft: &'a mut Account<'info, TrackNftState>,
+    track: &'a mut Account<'info, Track>,
+    license: Option<&'a mut Account<'info, License>>, // None for compressed editions
+    holder: AccountInfo<'info>, // License or cNFT owner, the buyer or a gift recipient
+    buyer: AccountInfo<'info>,
+    buyer_token_account: AccountInfo<'info>,
+    artist_token_account: AccountInfo<'info>,
+    creator_royalties: AccountInfo<'info>,
+    treasury: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    franchise: Option<&'a mut Account<'info, Franchise>>,
+    franchise_vault: Option<&'a Account<'info, TokenAccount>>,
+    edition_tree: Option<&'a mut Account<'info, EditionTree>>,
+    tree_config: Option<AccountInfo<'info>>,
+    merkle_tree: Option<AccountInfo<'info>>,
+    bubblegum_program: Option<AccountInfo<'info>>,
+    compression_program: Option<AccountInfo<'info>>,
+    log_wrapper: Option<AccountInfo<'info>>,
+    system_program: AccountInfo<'info>,
+    copyright_claim: Option<&'a mut Account<'info, CopyrightClaim>>,
+    dispute_escrow: Option<&'a Account<'info, TokenAccount>>,
+    pending_sale: Option<&'a mut Account<'info, PendingSale>>,
+    sale_escrow: Option<&'a Account<'info, TokenAccount>>,
+    leaderboard: Option<&'a AccountLoader<'info, Leaderboard>>,
+    parent_accounts: &'info [AccountInfo<'info>],
+}
+
+// Лицензия, статистика, сжатый экземпляр и выручка (сразу или в эскроу).
+// Возвращает (роялти, комиссию платформы).
+fn complete_purchase(purchase: Purchase<'_, '_>, track_price: u64, current_time: i64) -> Result<(u64, u64)> {
+    let Purchase {
+        track_nft,
+        track,
+        mut license,
+        holder,
+        buyer,
+        buyer_token_account,
+        artist_token_account,
+        creator_royalties,
+        treasury,
+        token_program,
+        franchise,
+        franchise_vault,
+        edition_tree,
+        tree_config,
+        merkle_tree,
+        bubblegum_program,
+        compression_program,
+        log_wrapper,
+        system_program,
+        copyright_claim,
+        dispute_escrow,
+        pending_sale,
+        sale_escrow,
+        leaderboard,
+        parent_accounts,
+    } = purchase;
+    
+    let royalty_amount = track_price
+        .checked_mul(track.royalty_percentage as u64)
+        .unwrap()
+        .checked_div(100)
+        .unwrap();
+    
+    let platform_fee = calculate_bps(track_price, track_nft.platform_fee_bps);
+    
+    // Выдаем лицензию (повторная покупка добавляет экземпляр).
+    // Сжатый экземпляр сам служит лицензией, PDA не создается; его держателей
+    // считает индексатор.
+    let mut is_new_holder = false;
+    if track.compressed_editions {
+        require!(license.is_none(), ErrorCode::UnexpectedAccount);
+    } else {
+        let license = license.as_mut().ok_or(ErrorCode::InvalidLicenseAccount)?;
+        is_new_holder = license.editions == 0;
+        license.track = track.key();
+        license.owner = holder.key();
+        license.price_paid = license.price_paid.checked_add(track_price).unwrap();
+        license.purchased_at = current_time;
+        license.editions = license.editions.checked_add(1).unwrap();
+        license.expires_at = 0;
+    }
+    
+    // Обновляем статистику продаж
+    track.sales_count = track.sales_count.checked_add(1).unwrap();
+    track.gross_revenue = track.gross_revenue.checked_add(track_price).unwrap();
+    if is_new_holder {
+        track.unique_buyers = track.unique_buyers.checked_add(1).unwrap();
+    }
+    
+    // Продажа идет в недельный чарт (копеечные продажи не накручивают рейтинг)
+    let week = chart_week_at(current_time);
+    if track_price >= CHART_MIN_SALE_PRICE && track.record_chart_activity(week, 0, 1) {
+        if let Some(leaderboard) = leaderboard {
+            leaderboard
+                .load_mut()?
+                .record(week, track.key(), track.week_plays, track.week_sales);
+        }
+    }
+    
+    // Экземпляр покупателя выпускается cNFT в текущее дерево артиста
+    let mut leaf_index = None;
+    if track.compressed_editions {
+        let edition_tree = edition_tree.ok_or(ErrorCode::MissingEditionTree)?;
+        let tree_config = tree_config.ok_or(ErrorCode::MissingEditionTree)?;
+        let merkle_tree = merkle_tree.ok_or(ErrorCode::MissingEditionTree)?;
+        let bubblegum_program = bubblegum_program.ok_or(ErrorCode::MissingEditionTree)?;
+        let compression_program = compression_program.ok_or(ErrorCode::MissingEditionTree)?;
+        let log_wrapper = log_wrapper.ok_or(ErrorCode::MissingEditionTree)?;
+        
+        leaf_index = Some(mint_compressed_edition(
+            edition_tree,
+            &tree_config,
+            &merkle_tree,
+            &bubblegum_program,
+            &compression_program,
+            &log_wrapper,
+            &system_program,
+            &buyer,
+            &holder,
+            track,
+            current_time,
+        )?);
+    }
+    
+    // Экземпляры кривой возвращаются через выкуп, окна возврата у них нет
+    if track_nft.refund_window > 0 && !track.has_curve {
+        // Выручка ждет в эскроу до закрытия окна возврата
+        let pending_sale = pending_sale.ok_or(ErrorCode::MissingRefundEscrow)?;
+        let sale_escrow = sale_escrow.ok_or(ErrorCode::MissingRefundEscrow)?;
+        
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: buyer_token_account.clone(),
+                    to: sale_escrow.to_account_info(),
+                    authority: buyer.clone(),
+                },
+            ),
+            track_price,
+        )?;
+        
+        pending_sale.track = track.key();
+        pending_sale.buyer = buyer.key();
+        pending_sale.holder = holder.key();
+        pending_sale.price = track_price;
+        pending_sale.royalty_amount = royalty_amount;
+        pending_sale.platform_fee = platform_fee;
+        pending_sale.edition = track.sales_count;
+        pending_sale.compressed = leaf_index.is_some();
+        pending_sale.leaf_index = leaf_index.unwrap_or(0);
+        pending_sale.edition_tree = if leaf_index.is_some() {
+            track.edition_tree
+        } else {
+            Pubkey::default()
+        };
+        pending_sale.purchased_at = current_time;
+        pending_sale.refundable_until = current_time.checked_add(track_nft.refund_window).unwrap();
+        if let Some(license) = license.as_mut() {
+            license.pending_refunds = license.pending_refunds.checked_add(1).unwrap();
+        }
+    } else {
+        // Лишний эскроу не создаем: покупатель заплатил бы аренду впустую
+        require!(pending_sale.is_none(), ErrorCode::UnexpectedAccount);
+        
+        distribute_sale_proceeds(
+            track,
+            track_nft,
+            SaleProceeds {
+                from: buyer_token_account,
+                authority: buyer,
+                signer: &[],
+                token_program,
+                artist_token_account,
+                creator_royalties,
+                treasury,
+                franchise,
+                franchise_vault,
+                copyright_claim,
+                dispute_escrow,
+                parent_accounts,
+            },
+            track_price,
+            royalty_amount,
+            platform_fee,
+            current_time,
+        )?;
+    }
+    
+    Ok((royalty_amount, platform_fee))
+}
+
+// Счета получателей выручки от продажи трека
+struct SaleProceeds<'a, 'info> {
+    from: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer: &'a [&'a [&'a [u8]]],
+    token_program: AccountInfo<'info>,
+    artist_token_account: AccountInfo<'info>,
+    creator_royalties: AccountInfo<'info>,
+    treasury: AccountInfo<'info>,
+    franchise: Option<&'a mut Account<'info, Franchise>>,
+    franchise_vault: Option<&'a Account<'info, TokenAccount>>,
+    copyright_claim: Option<&'a mut Account<'info, CopyrightClaim>>,
+    dispute_escrow: Option<&'a Account<'info, TokenAccount>>,
+    parent_accounts: &'info [AccountInfo<'info>],
+}
+
+// Распределение цены продажи: доли ремикса и франшизы, артист и роялти
+// (или эскроу спора), комиссия платформы
+fn distribute_sale_proceeds<'info>(
+    track: &mut Account<'info, Track>,
+    track_nft: &mut Account<'info, TrackNftState>,
+    proceeds: SaleProceeds<'_, 'info>,
+    price: u64,
+    royalty_amount: u64,
+    platform_fee: u64,
+    current_time: i64,
+) -> Result<()> {
+    let mut artist_amount = price
+        .checked_sub(royalty_amount)
+        .unwrap()
+        .checked_sub(platform_fee)
+        .unwrap();
+    
+    // Доли родительских треков (ремикс) от дохода артиста
+    let upstream_paid = pay_upstream_royalties(
+        track,
+        proceeds.parent_accounts,
+        &track_nft.payment_mint,
+        &proceeds.from,
+        &proceeds.authority,
+        proceeds.signer,
+        &proceeds.token_program,
+        artist_amount,
+        current_time,
+    )?;
+    artist_amount = artist_amount.checked_sub(upstream_paid).unwrap();
+    
+    // Доля держателей франшизы поступает в хранилище трека
+    if track.has_franchise {
+        let franchise_cut = pay_franchise_cut(
+            proceeds.franchise,
+            proceeds.franchise_vault,
+            &proceeds.from,
+            &proceeds.authority,
+            proceeds.signer,
+            &proceeds.token_program,
+            artist_amount,
+        )?;
+        artist_amount = artist_amount.checked_sub(franchise_cut).unwrap();
+    }
+    
+    let transfer = |to: &AccountInfo<'info>, amount: u64| -> Result<()> {
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                proceeds.token_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: proceeds.from.clone(),
+                    to: to.clone(),
+                    authority: proceeds.authority.clone(),
+                },
+                proceeds.signer,
+            ),
+            amount,
+        )
+    };
+    
+    if track.taken_down {
+        // Трек снят по иску: вся доля создателя принадлежит правообладателю,
+        // который после resolve_claim стал payout_wallet трека
+        transfer(
+            &proceeds.artist_token_account,
+            artist_amount.checked_add(royalty_amount).unwrap(),
+        )?;
+    } else if track.under_dispute {
+        // Во время спора доля создателя удерживается в эскроу
+        escrow_disputed_proceeds(
+            proceeds.copyright_claim,
+            proceeds.dispute_escrow,
+            &proceeds.from,
+            &proceeds.authority,
+            proceeds.signer,
+            &proceeds.token_program,
+            artist_amount.checked_add(royalty_amount).unwrap(),
+        )?;
+    } else {
+        // Переводим средства артисту
+        transfer(&proceeds.artist_token_account, artist_amount)?;
+        
+        // Выплачиваем роялти на роялти-счет создателя
+        if royalty_amount > 0 {
+            transfer(&proceeds.creator_royalties, royalty_amount)?;
+            track.total_royalties_paid = track.total_royalties_paid.checked_add(royalty_amount).unwrap();
+        }
+    }
+    
+    // Выплачиваем комиссию в казну протокола
+    transfer(&proceeds.treasury, platform_fee)?;
+    track_nft.total_fees_collected = track_nft.total_fees_collected.checked_add(platform_fee).unwrap();
+    
+    Ok(())
+}
+
+// Удержание доли создателя в эскроу спора
+fn escrow_disputed_proceeds<'info>(
+    claim: Option<&mut Account<'info, CopyrightClaim>>,
+    dispute_escrow: Option<&Account<'info, TokenAccount>>,
+    from: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let claim = claim.ok_or(ErrorCode::MissingDisputeAccounts)?;
+    let dispute_escrow = dispute_escrow.ok_or(ErrorCode::MissingDisputeAccounts)?;
+    
+    if amount > 0 {
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: from.clone(),
+                    to: dispute_escrow.to_account_info(),
+                    authority: authority.clone(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        claim.escrowed = claim.escrowed.checked_add(amount).unwrap();
+    }
+    
+    Ok(())
+}
+
+// Перевод доли держателей франшизы в хранилище трека
+fn pay_franchise_cut<'info>(
+    franchise: Option<&mut Account<'info, Franchise>>,
+    franchise_vault: Option<&Account<'info, TokenAccount>>,
+    from: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &AccountInfo<'info>,
+    artist_amount: u64,
+) -> Result<u64> {
+    let franchise = franchise.ok_or(ErrorCode::MissingFranchiseAccounts)?;
+    let franchise_vault = franchise_vault.ok_or(ErrorCode::MissingFranchiseAccounts)?;
+    
+    let franchise_cut = calculate_bps(artist_amount, franchise.revenue_share_bps);
+    if franchise_cut > 0 {
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: from.clone(),
+                    to: franchise_vault.to_account_info(),
+                    authority: authority.clone(),
+                },
+                signer,
+            ),
+            franchise_cut,
+        )?;
+        franchise.accrue(franchise_cut);
+    }
+    
+    Ok(franchise_cut)
+}
+
+// Выпуск сжатого экземпляра через Bubblegum в дерево артиста.
+// Делегат листа - PDA дерева, чтобы при возврате экземпляр можно было сжечь.
+#[allow(clippy::too_many_arguments)]
+fn mint_compressed_edition<'info>(
+    edition_tree: &mut Account<'info, EditionTree>,
+    tree_config: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    bubblegum_program: &AccountInfo<'info>,
+    compression_program: &AccountInfo<'info>,
+    log_wrapper: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    track: &Account<'info, Track>,
+    current_time: i64,
+) -> Result<u64> {
+    require!(merkle_tree.key() == edition_tree.merkle_tree, ErrorCode::InvalidEditionTree);
+    require!(edition_tree.minted < edition_tree.capacity, ErrorCode::EditionTreeFull);
+    
+    // Bubblegum нумерует листья по порядку выпуска, дерево пишет только эта программа
+    let leaf_index = edition_tree.minted;
+    let metadata = MetadataArgs {
+        name: edition_name(&track.track_name),
+        symbol: EDITION_SYMBOL.to_string(),
+        uri: format!("ipfs://{}", track.ipfs_hash),
+        seller_fee_basis_points: track.royalty_percentage as u16 * 100,
+        primary_sale_happened: true,
+        is_mutable: false,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        token_program_version: TokenProgramVersion::Original,
+        creators: vec![Creator {
+            address: track.creator,
+            verified: false,
+            share: 100,
+        }],
+    };
+    
+    let artist = edition_tree.artist;
+    let index_bytes = edition_tree.index.to_le_bytes();
+    let seeds = &[b"edition_tree".as_ref(), artist.as_ref(), index_bytes.as_ref(), &[edition_tree.bump]];
+    let signer = &[&seeds[..]];
+    let edition_tree_info = edition_tree.to_account_info();
+    
+    MintV1CpiBuilder::new(bubblegum_program)
+        .tree_config(tree_config)
+        .leaf_owner(owner)
+        .leaf_delegate(&edition_tree_info)
+        .merkle_tree(merkle_tree)
+        .payer(payer)
+        .tree_creator_or_delegate(&edition_tree_info)
+        .log_wrapper(log_wrapper)
+        .compression_program(compression_program)
+        .system_program(system_program)
+        .metadata(metadata)
+        .invoke_signed(signer)?;
+    
+    emit!(CompressedEditionMintedEvent {
+        merkle_tree: edition_tree.merkle_tree,
+        leaf_index,
+        track: track.key(),
+        owner: owner.key(),
+        edition: track.sales_count,
+        timestamp: current_time,
+    });
+    
+    edition_tree.minted = edition_tree.minted.checked_add(1).unwrap();
+    
+    Ok(leaf_index)
+}
+
+// Имя экземпляра: название трека в пределах лимита Bubblegum
+fn edition_name(track_name: &str) -> String {
+    let mut end = track_name.len().min(MAX_EDITION_NAME_LEN);
+    while !track_name.is_char_boundary(end) {
+        end -= 1;
+    }
+    track_name[..end].to_string()
+}
+
+// Купленная лицензия бессрочна, арендная действует до expires_at
+fn license_has_access(license: &License, current_time: i64) -> bool {
+    if license.expires_at == 0 {
+        license.editions > 0
+    } else {
+        current_time < license.expires_at
+    }
+}
+
+// Выдача лицензии [b"license", track, owner] внутри пакетной покупки.
+// Если лицензия уже есть, добавляется экземпляр. Возвращает true для нового держателя.
+fn issue_license<'info>(
+    license_info: &AccountInfo<'info>,
+    track: &Pubkey,
+    owner: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    price_paid: u64,
+    current_time: i64,
+) -> Result<bool> {
+    let (expected, bump) =
+        Pubkey::find_program_address(&[b"license", track.as_ref(), owner.as_ref()], &crate::ID);
+    require!(license_info.key() == expected, ErrorCode::InvalidLicenseAccount);
+    
+    if !license_info.data_is_empty() {
+        require!(license_info.owner == &crate::ID, ErrorCode::InvalidLicenseAccount);
+        let mut data = license_info.try_borrow_mut_data()?;
+        let mut license = License::try_deserialize(&mut &data[..])?;
+        let is_new_holder = license.editions == 0; // Rental converted to a purchase
+        license.price_paid = license.price_paid.checked_add(price_paid).unwrap();
+        license.purchased_at = current_time;
+        license.editions = license.editions.checked_add(1).unwrap();
+        license.expires_at = 0;
+        let mut writer: &mut [u8] = &mut data[..];
+        license.try_serialize(&mut writer)?;
+        return Ok(is_new_holder);
+    }
+    
+    let seeds = &[b"license".as_ref(), track.as_ref(), owner.as_ref(), &[bump]];
+    create_pda_account(license_info, payer, system_program, LICENSE_ACCOUNT_SPACE, &[&seeds[..]])?;
+    
+    let license = License {
+        track: *track,
+        owner: *owner,
+        price_paid,
+        purchased_at: current_time,
+        editions: 1,
+        expires_at: 0,
+        pending_refunds: 0,
+    };
+    let mut data = license_info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    license.try_serialize(&mut writer)?;
+    
+    Ok(true)
+}
+
+// Доли сплит-листа: уникальные получатели, сумма не больше 100%
+fn validate_split_recipients(recipients: &[SplitRecipient]) -> Result<()> {
+    require!(recipients.len() <= MAX_SPLIT_RECIPIENTS, ErrorCode::InvalidSplitSheet);
+    
+    let mut total_bps: u64 = 0;
+    for (i, recipient) in recipients.iter().enumerate() {
+        require!(recipient.share_bps > 0, ErrorCode::InvalidSplitSheet);
+        require!(
+            !recipients[..i].iter().any(|r| r.wallet == recipient.wallet),
+            ErrorCode::InvalidSplitSheet
+        );
+        total_bps = total_bps.checked_add(recipient.share_bps as u64).unwrap();
+    }
+    require!(total_bps <= BPS_DENOMINATOR, ErrorCode::InvalidSplitSheet);
+    
+    Ok(())
+}
+
+// Программа мемориалов ушедших артистов
+fn memorial_program_id() -> Pubkey {
+    Pubkey::from_str("AQ2zXikPKhLsAKLgUDY1j2asJnkcekE9omjv9SkULxqU").unwrap()
+}
+
+// Подписант должен быть PDA мемориала артиста; статус мемориала проверяет его программа
+fn require_memorial_signer(memorial: &Pubkey, artist: &Pubkey) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"memorial", artist.as_ref()],
+        &memorial_program_id(),
+    );
+    require!(*memorial == expected, ErrorCode::Unauthorized);
+    Ok(())
+}
+
+// Неделя чарта для момента времени и для часовой эпохи прослушиваний
+fn chart_week_at(timestamp: i64) -> u64 {
+    (timestamp / CHART_WEEK_SECONDS) as u64
+}
+
+fn chart_week_for_epoch(epoch: u64) -> u64 {
+    epoch
+        .checked_mul(PLAY_EPOCH_SECONDS as u64)
+        .unwrap()
+        .checked_div(CHART_WEEK_SECONDS as u64)
+        .unwrap()
+}
+
+// Вес места в пуле наград чарта (место 0 - первое)
+fn chart_reward_weight(curve: RewardCurve, decay_bps: u16, ranks_paid: u8, rank: u8) -> u128 {
+    match curve {
+        RewardCurve::Flat => CURVE_SCALE,
+        RewardCurve::Linear => CURVE_SCALE * (ranks_paid - rank) as u128,
+        RewardCurve::Geometric => {
+            let mut weight = CURVE_SCALE;
+            for _ in 0..rank {
+                weight = weight * decay_bps as u128 / BPS_DENOMINATOR as u128;
+            }
+            weight
+        }
+    }
+}
+
+// Период подписки, к которому относится часовая эпоха прослушиваний
+fn subscription_period_for_epoch(epoch: u64) -> u64 {
+    epoch
+        .checked_mul(PLAY_EPOCH_SECONDS as u64)
+        .unwrap()
+        .checked_div(SUBSCRIPTION_PERIOD_SECONDS as u64)
+        .unwrap()
+}
+
+// Счетчик прослушиваний трека за период (создается при первом начислении)
+fn record_period_plays<'info>(
+    period_plays_info: &AccountInfo<'info>,
+    period: u64,
+    track: &Pubkey,
+    plays: u64,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let period_bytes = period.to_le_bytes();
+    let (expected, bump) = Pubkey::find_program_address(
+        &[b"period_plays", period_bytes.as_ref(), track.as_ref()],
+        &crate::ID,
+    );
+    require!(period_plays_info.key() == expected, ErrorCode::InvalidSettlementBatch);
+    
+    let mut period_plays = if period_plays_info.data_is_empty() {
+        let seeds = &[b"period_plays".as_ref(), period_bytes.as_ref(), track.as_ref(), &[bump]];
+        create_pda_account(period_plays_info, payer, system_program, PERIOD_PLAYS_SPACE, &[&seeds[..]])?;
+        PeriodPlays {
+            period,
+            track: *track,
+            plays: 0,
+        }
+    } else {
+        require!(period_plays_info.owner == &crate::ID, ErrorCode::InvalidSettlementBatch);
+        PeriodPlays::try_deserialize(&mut &period_plays_info.try_borrow_data()?[..])?
+    };
+    period_plays.plays = period_plays.plays.checked_add(plays).unwrap();
+    
+    let mut data = period_plays_info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    period_plays.try_serialize(&mut writer)?;
+    
+    Ok(())
+}
+
+// Создание PDA программы. Заранее пополненный адрес не принимает create_account,
+// поэтому рента доплачивается, а место и владелец назначаются отдельно.
+fn create_pda_account<'info>(
+    target: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    
+    if target.lamports() == 0 {
+        return anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                anchor_lang::system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: target.clone(),
+                },
+                signer,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        );
+    }
+    
+    let top_up = rent.saturating_sub(target.lamports());
+    if top_up > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: target.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::Allocate {
+                account_to_allocate: target.clone(),
+            },
+            signer,
+        ),
+        space as u64,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::Assign {
+                account_to_assign: target.clone(),
+            },
+            signer,
+        ),
+        &crate::ID,
+    )
+}
+
+// Оплата подписки: комиссия платформы копится для казны, остаток делится между
+// периодами оплаченного срока (начиная с периода starts_at). Доля текущего периода
+// идет в его пул, доли будущих откладываются в график.
+#[allow(clippy::too_many_arguments)]
+fn pay_subscription<'info>(
+    track_nft: &mut Account<'info, TrackNftState>,
+    subscription_period: &mut Account<'info, SubscriptionPeriod>,
+    schedule: &mut Account<'info, SubscriptionSchedule>,
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    stream_vault: &Account<'info, TokenAccount>,
+    subscriber: &Signer<'info>,
+    plan: &Account<'info, SubscriptionPlan>,
+    starts_at: i64,
+    current_time: i64,
+) -> Result<()> {
+    let current_period = (current_time / SUBSCRIPTION_PERIOD_SECONDS) as u64;
+    require!(subscription_period.period == current_period, ErrorCode::InvalidSubscriptionPeriod);
+    
+    let first_period = (starts_at / SUBSCRIPTION_PERIOD_SECONDS) as u64;
+    let periods = (plan.duration as u64).div_ceil(SUBSCRIPTION_PERIOD_SECONDS as u64);
+    // Предоплата не должна догнать слот еще не открытого периода в кольце
+    require!(
+        first_period - current_period + periods < SUBSCRIPTION_SCHEDULE_PERIODS as u64,
+        ErrorCode::SubscriptionPrepaidTooFar
+    );
+    
+    let price = plan.price;
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: from.to_account_info(),
+                to: stream_vault.to_account_info(),
+                authority: subscriber.to_account_info(),
+            },
+        ),
+        price,
+    )?;
+    
+    let platform_fee = calculate_bps(price, track_nft.platform_fee_bps);
+    let pool_amount = price.checked_sub(platform_fee).unwrap();
+    
+    track_nft.pending_stream_fees = track_nft.pending_stream_fees.checked_add(platform_fee).unwrap();
+    
+    // Остаток от деления достается первому периоду
+    let per_period = pool_amount / periods;
+    let mut first_share = pool_amount - per_period * (periods - 1);
+    for period in first_period..first_period + periods {
+        let share = std::mem::replace(&mut first_share, per_period);
+        if period == current_period {
+            subscription_period.pool_amount = subscription_period.pool_amount.checked_add(share).unwrap();
+        } else {
+            let slot = (period % SUBSCRIPTION_SCHEDULE_PERIODS as u64) as usize;
+            schedule.amounts[slot] = schedule.amounts[slot].checked_add(share).unwrap();
+            schedule.deferred_total = schedule.deferred_total.checked_add(share).unwrap();
+        }
+    }
+    
+    Ok(())
+}
+
+// Лист дерева квитанций воспроизведений: (listener, track, epoch, nonce)
+fn play_receipt_leaf(listener: &Pubkey, track: &Pubkey, epoch: u64, nonce: u64) -> [u8; 32] {
+    hashv(&[
+        listener.as_ref(),
+        track.as_ref(),
+        &epoch.to_le_bytes(),
+        &nonce.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+// Проверка Merkle-доказательства (пары хэшируются в отсортированном порядке)
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed = leaf;
+    for node in proof.iter() {
+        computed = if computed <= *node {
+            hashv(&[computed.as_ref(), node.as_ref()]).to_bytes()
+        } else {
+            hashv(&[node.as_ref(), computed.as_ref()]).to_bytes()
+        };
+    }
+    computed == root
+}
+
+// Сообщение, которое подписывает оракул воспроизведений
+fn play_attestation_message(track: &Pubkey, listener: &Pubkey, nonce: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 32 + 8);
+    message.extend_from_slice(track.as_ref());
+    message.extend_from_slice(listener.as_ref());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+// Ключ закрепленного имени артиста (без учета регистра)
+fn artist_name_hash(display_name: &str) -> [u8; 32] {
+    hashv(&[display_name.to_lowercase().as_bytes()]).to_bytes()
+}
+
+// Имя, закрепленное за верифицированным артистом, доступно только ему
+fn check_artist_name_claim(name_claim: &AccountInfo, creator: &Pubkey) -> Result<()> {
+    if name_claim.data_is_empty() {
+        return Ok(());
+    }
+    require!(name_claim.owner == &crate::ID, ErrorCode::InvalidArtistProfile);
+    let claim = ArtistNameClaim::try_deserialize(&mut &name_claim.try_borrow_data()?[..])?;
+    require!(
+        claim.creator == Pubkey::default() || claim.creator == *creator,
+        ErrorCode::ArtistNameTaken
+    );
+    Ok(())
+}
+
+// Сообщение аттестации отпечатка:
+// domain || program_id || fingerprint || creator || ipfs_hash || parents
+fn content_attestation_message(
+    fingerprint: &[u8; 32],
+    creator: &Pubkey,
+    ipfs_hash: &str,
+    parents: &[Pubkey],
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(
+        CONTENT_ATTESTATION_DOMAIN.len() + 32 + 32 + 32 + ipfs_hash.len() + 32 * parents.len(),
+    );
+    message.extend_from_slice(CONTENT_ATTESTATION_DOMAIN);
+    message.extend_from_slice(crate::ID.as_ref());
+    message.extend_from_slice(fingerprint);
+    message.extend_from_slice(creator.as_ref());
+    message.extend_from_slice(ipfs_hash.as_bytes());
+    for parent in parents {
+        message.extend_from_slice(parent.as_ref());
+    }
+    message
+}
+
+// Проверяет, что предыдущая инструкция транзакции - это ed25519-проверка
+// подписи `signer` над `message` (данные внутри самой инструкции).
+fn verify_ed25519_attestation(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = ix_sysvar::load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::MissingAttestation);
+    
+    let ed25519_ix = ix_sysvar::load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+    require!(ed25519_ix.program_id == ed25519_program::ID, ErrorCode::MissingAttestation);
+    require!(ed25519_ix.accounts.is_empty(), ErrorCode::InvalidAttestation);
+    
+    let data = &ed25519_ix.data;
+    // Заголовок: количество подписей (1 байт), выравнивание (1 байт), смещения (14 байт)
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidAttestation);
+    
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6) as usize;
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+    let message_ix_index = read_u16(14);
+    
+    // Все данные должны находиться в самой ed25519-инструкции
+    require!(
+        signature_ix_index == u16::MAX && public_key_ix_index == u16::MAX && message_ix_index == u16::MAX,
+        ErrorCode::InvalidAttestation
+    );
+    require!(
+        data.len() >= public_key_offset + 32 && data.len() >= message_offset + message_size,
+        ErrorCode::InvalidAttestation
+    );
+    require!(
+        &data[public_key_offset..public_key_offset + 32] == signer.as_ref(),
+        ErrorCode::InvalidAttestation
+    );
+    require!(
+        &data[message_offset..message_offset + message_size] == message,
+        ErrorCode::InvalidAttestation
+    );
+    
+    Ok(())
+}
+
+// Accounts
+#[account]
+pub struct TrackNftState {
+    pub authority: Pubkey,
+    pub royalty_percentage: u8, // Default royalty percentage
+    pub total_tracks: u64,
+    pub platform_fee_bps: u16,  // Platform fee in basis points
+    pub payment_mint: Pubkey,   // Mint accepted for purchases
+    pub treasury: Pubkey,       // Protocol treasury PDA (only fee destination)
+    pub treasury_bump: u8,
+    pub total_fees_collected: u64,
+    pub play_oracle: Pubkey,    // Backend key attesting plays
+    pub min_play_interval: i64, // Seconds between plays of one listener
+    pub max_daily_plays: u32,   // Plays per listener per 24h window
+    pub last_settled_epoch: u64,
+    pub stream_vault_bump: u8,
+    pub pending_stream_fees: u64, // Stream fees held in the vault, not yet swept
+    pub tip_fee_bps: u16,         // Reduced platform fee for tips
+    pub content_oracle: Pubkey,   // Anti-piracy API key attesting audio fingerprints
+    pub arbiter: Pubkey,          // Resolves copyright claims
+    pub claim_bond: u64,          // Bond posted with every copyright claim
+    pub refund_window: i64,       // Seconds sale proceeds stay refundable, 0 = paid at once
+    pub sale_escrow_bump: u8,
+}
+
+#[account]
+pub struct Track {
+    pub track_name: String,
+    pub artist_name: String,
+    pub ipfs_hash: String,
+    pub price: u64,
+    pub royalty_percentage: u8,
+    pub is_listed: bool,
+    pub play_count: u64,
+    pub like_count: u64,
+    pub total_royalties_paid: u64,
+    pub creator: Pubkey,
+    pub mint_time: i64,
+    pub sales_count: u64,      // Number of purchases
+    pub gross_revenue: u64,    // Sum of purchase prices
+    pub unique_buyers: u64,    // Distinct license holders
+    pub layout_version: u8,
+    pub stream_price: u64,     // Price per play_track call, 0 = free; batched plays are never charged
+    pub stats_period: u64,     // Subscription period of period_plays
+    pub period_plays: u64,     // Settled plays in stats_period
+    pub prev_period_plays: u64, // Settled plays in stats_period - 1
+    pub has_drop: bool,        // Sales follow the DropConfig phases
+    pub has_franchise: bool,   // Part of revenue flows to the Franchise vault
+    pub payout_wallet: Pubkey, // Receives sale proceeds, creator unless redirected
+    pub upstream: Vec<UpstreamShare>, // Parent tracks of a remix, paid on every sale
+    pub rental_price: u64,
+    pub rental_duration: i64,  // Seconds of access per rental, 0 = rentals disabled
+    pub compressed_editions: bool, // Sales append a cNFT leaf to the artist's EditionTree
+    pub under_dispute: bool,   // Creator proceeds go to the dispute escrow
+    pub refund_count: u64,     // Purchases refunded within the refund window
+    pub transferable: bool,    // Holders may pass perpetual licenses on
+    pub has_curve: bool,       // Price follows the BondingCurve
+    pub chart_week: u64,       // Week of week_plays and week_sales
+    pub week_plays: u64,       // Settled plays in chart_week
+    pub week_sales: u64,       // Sales in chart_week
+    pub artist_profile: Pubkey, // ArtistProfile the track was created under, default = none
+    pub taken_down: bool,      // Copyright claim upheld, the track can never be sold again
+    pub fingerprint: [u8; 32], // Key of the track's ContentRecord, zero for legacy tracks
+    pub edition_tree: Pubkey,  // EditionTree receiving new compressed editions
+}
+
+impl Track {
+    // Недельные счетчики чарта; активность прошедших недель не учитывается
+    pub fn record_chart_activity(&mut self, week: u64, plays: u64, sales: u64) -> bool {
+        if week < self.chart_week {
+            return false;
+        }
+        if week > self.chart_week {
+            self.chart_week = week;
+            self.week_plays = 0;
+            self.week_sales = 0;
+        }
+        self.week_plays = self.week_plays.checked_add(plays).unwrap();
+        self.week_sales = self.week_sales.checked_add(sales).unwrap();
+        true
+    }
+    
+    // Возвращенная продажа снимается с чарта, если ее неделя еще идет
+    pub fn revert_chart_sale(&mut self, week: u64) -> bool {
+        if week != self.chart_week || self.week_sales == 0 {
+            return false;
+        }
+        self.week_sales -= 1;
+        true
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpstreamShare {
+    pub parent_track: Pubkey,
+    pub share_bps: u16, // Of the artist amount
+}
+
+#[account]
+pub struct PendingSale {
+    pub track: Pubkey,
+    pub buyer: Pubkey,       // Payer, refunds go back to this wallet
+    pub holder: Pubkey,      // License holder, the buyer or a gift recipient
+    pub price: u64,
+    pub royalty_amount: u64, // Split fixed at purchase time
+    pub platform_fee: u64,
+    pub edition: u64,        // Track sales_count after this purchase
+    pub compressed: bool,    // A cNFT was minted for this edition instead of a license
+    pub leaf_index: u64,
+    pub edition_tree: Pubkey, // EditionTree holding the cNFT
+    pub purchased_at: i64,
+    pub refundable_until: i64,
+}
+
+#[account]
+pub struct CopyrightClaim {
+    pub track: Pubkey,
+    pub claimant: Pubkey,
+    pub evidence_hash: [u8; 32], // Hash of the off-chain claim evidence
+    pub bond: u64,
+    pub escrowed: u64,           // Creator proceeds withheld since filing
+    pub filed_at: i64,
+}
+
+#[account]
+pub struct ContentRecord {
+    pub fingerprint: [u8; 32], // Perceptual audio fingerprint hash
+    pub track: Pubkey,         // First track registered with this audio
+    pub creator: Pubkey,
+    pub registered_at: i64,
+}
+
+#[account]
+pub struct EditionTree {
+    pub artist: Pubkey,
+    pub index: u32,          // Artist's trees are numbered, a full tree is replaced by the next
+    pub merkle_tree: Pubkey, // Bubblegum tree, this PDA is its creator and leaf delegate
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    pub capacity: u64,
+    pub minted: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct RemixLicense {
+    pub parent_track: Pubkey,
+    pub remixer: Pubkey,
+    pub share_bps: u16,       // Upstream share the parent creator requires
+    pub derivatives: u32,     // Tracks registered under this license
+    pub max_derivatives: u32, // Cap on derivatives, 0 = unlimited
+    pub granted_at: i64,
+}
+
+#[account]
+pub struct BondingCurve {
+    pub track: Pubkey,
+    pub kind: CurveKind,
+    pub base_price: u64,     // Price of the first edition
+    pub slope: u64,          // Linear: added per edition, exponential: growth bps per edition
+    pub reserve_bps: u16,    // Share of each sale kept for buybacks
+    pub supply: u64,         // Curve editions outstanding
+    pub reserve: u64,        // Tokens held in the curve reserve
+    pub buybacks: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CurveKind {
+    Linear,
+    Exponential,
+}
+
+impl BondingCurve {
+    // Цена экземпляра номер supply (с нуля); неубывающая по supply
+    pub fn price_at(&self, supply: u64) -> u64 {
+        match self.kind {
+            CurveKind::Linear => self
+                .base_price
+                .saturating_add(self.slope.saturating_mul(supply)),
+            CurveKind::Exponential => {
+                // base * (1 + slope / 10000) ^ supply возведением в квадрат
+                let mut factor = CURVE_SCALE * (BPS_DENOMINATOR + self.slope) as u128 / BPS_DENOMINATOR as u128;
+                let mut multiplier = CURVE_SCALE;
+                let mut exponent = supply;
+                while exponent > 0 {
+                    if exponent & 1 == 1 {
+                        multiplier = multiplier.saturating_mul(factor) / CURVE_SCALE;
+                    }
+                    factor = factor.saturating_mul(factor) / CURVE_SCALE;
+                    exponent >>= 1;
+                }
+                let price = (self.base_price as u128).saturating_mul(multiplier) / CURVE_SCALE;
+                price.min(u64::MAX as u128) as u64
+            }
+        }
+    }
+}
+
+#[account]
+pub struct Coupon {
+    pub artist: Pubkey,
+    pub coupon_id: u64,
+    pub track: Pubkey,            // Default = any track of the artist
+    pub kind: DiscountKind,
+    pub value: u64,               // Basis points or a fixed amount off
+    pub max_uses: u32,
+    pub max_uses_per_wallet: u32, // 0 = no per-wallet limit
+    pub uses: u32,
+    pub expires_at: i64,          // 0 = no expiry
+    pub code_hash: [u8; 32],      // sha256 of the secret code, zero = no code
+    pub total_discount: u64,      // Sum of discounts granted
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DiscountKind {
+    Percentage,
+    Fixed,
+}
+
+#[account]
+pub struct CouponRedemption {
+    pub coupon: Pubkey,
+    pub buyer: Pubkey,
+    pub uses: u32, // Discounts this wallet received from the coupon
+    pub bump: u8,
+}
+
+impl Coupon {
+    pub fn discount_for(&self, price: u64) -> u64 {
+        match self.kind {
+            DiscountKind::Percentage => calculate_bps(price, self.value as u16),
+            DiscountKind::Fixed => self.value.min(price),
+        }
+    }
+}
+
+#[account]
+pub struct License {
+    pub track: Pubkey,
+    pub owner: Pubkey,
+    pub price_paid: u64,     // Total paid for all editions
+    pub purchased_at: i64,   // Time of the latest purchase
+    pub editions: u32,       // Editions held by the owner
+    pub expires_at: i64,     // Rental expiry, 0 = perpetual
+    pub pending_refunds: u32, // Editions still refundable from the sale escrow
+}
+
+#[account]
+pub struct DropConfig {
+    pub track: Pubkey,
+    pub allowlist_root: [u8; 32], // Merkle root of sha256(wallet) leaves
+    pub presale_price: u64,
+    pub max_per_wallet: u32,      // Editions per wallet during the allowlist phase
+    pub allowlist_start: i64,
+    pub allowlist_end: i64,
+    pub public_start: i64,
+    pub public_end: i64,          // 0 = no end
+    pub presale_supply: u64,      // Editions on sale during the allowlist phase, 0 = no cap
+    pub presale_sold: u64,
+}
+
+#[account]
+pub struct DropBuyer {
+    pub track: Pubkey,
+    pub buyer: Pubkey,
+    pub purchased: u32, // Editions bought during the allowlist phase
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DropPhase {
+    Allowlist,
+    Public,
+    Closed,
+}
+
+impl DropConfig {
+    pub fn phase_at(&self, timestamp: i64) -> DropPhase {
+        if timestamp >= self.allowlist_start && timestamp < self.allowlist_end {
+            DropPhase::Allowlist
+        } else if timestamp >= self.public_start && (self.public_end == 0 || timestamp < self.public_end) {
+            DropPhase::Public
+        } else {
+            DropPhase::Closed
+        }
+    }
+}
+
+#[account]
+pub struct ListenerState {
+    pub listener: Pubkey,
+    pub last_nonce: u64,     // Highest attestation nonce consumed
+    pub last_play_time: i64,
+    pub window_start: i64,   // Start of the current 24h rate-limit window
+    pub plays_in_window: u32,
+    pub total_plays: u64,
+    pub balance: u64,        // Prepaid NDT balance held in the stream vault
+    pub total_spent: u64,
+}
+
+#[account(zero_copy)]
+pub struct Leaderboard {
+    pub week: u64,
+    pub entry_count: u64,
+    pub entries: [ChartEntry; CHART_SIZE], // Unordered top tracks of the week
+}
+
+#[account(zero_copy)]
+pub struct ChartSnapshot {
+    pub week: u64,
+    pub entry_count: u64,
+    pub finalized_at: i64,
+    pub entries: [ChartEntry; CHART_SIZE], // Sorted by score, highest first
+}
+
+#[zero_copy]
+pub struct ChartEntry {
+    pub track: Pubkey,
+    pub plays: u64,
+    pub sales: u64,
+    pub score: u64, // plays + sales * CHART_SALE_WEIGHT
+}
+
+impl Leaderboard {
+    // Обновляет счет трека; новый трек вытесняет худший, если набрал больше
+    pub fn record(&mut self, week: u64, track: Pubkey, plays: u64, sales: u64) {
+        if week != self.week {
+            return;
+        }
+        let score = plays.saturating_add(sales.saturating_mul(CHART_SALE_WEIGHT));
+        let count = self.entry_count as usize;
+        let slot = match self.entries[..count].iter().position(|entry| entry.track == track) {
+            Some(index) => index,
+            None if count < CHART_SIZE => {
+                self.entry_count += 1;
+                count
+            }
+            None => {
+                let (index, lowest) = self
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, entry)| entry.score)
+                    .unwrap();
+                if score <= lowest.score {
+                    return;
+                }
+                index
+            }
+        };
+        self.entries[slot] = ChartEntry {
+            track,
+            plays,
+            sales,
+            score,
+        };
+    }
+}
+
+#[account]
+pub struct ChartRewardConfig {
+    pub curve: RewardCurve,
+    pub decay_bps: u16,        // Geometric curve: weight of a rank relative to the one above
+    pub ranks_paid: u8,        // Top ranks sharing each weekly pool
+    pub weekly_budget: u64,    // Upper bound of a weekly pool
+    pub min_play_count: u64,   // Lifetime plays a track needs to claim
+    pub min_like_count: u64,   // Lifetime likes a track needs to claim
+    pub outstanding: u64,      // Allocated to open pools and not yet claimed
+    pub total_paid: u64,
+    pub ndt_mint: Pubkey,      // Mint of the reward vault
+    pub vault_bump: u8,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RewardCurve {
+    Flat,
+    Linear,
+    Geometric,
+}
+
+impl ChartRewardConfig {
+    pub fn apply(
+        &mut self,
+        curve: RewardCurve,
+        decay_bps: u16,
+        ranks_paid: u8,
+        weekly_budget: u64,
+        min_play_count: u64,
+        min_like_count: u64,
+    ) -> Result<()> {
+        require!(
+            ranks_paid > 0 && ranks_paid as usize <= CHART_SIZE,
+            ErrorCode::InvalidChartRewardParams
+        );
+        require!(
+            curve != RewardCurve::Geometric || (decay_bps > 0 && decay_bps as u64 <= BPS_DENOMINATOR),
+            ErrorCode::InvalidChartRewardParams
+        );
+        
+        self.curve = curve;
+        self.decay_bps = decay_bps;
+        self.ranks_paid = ranks_paid;
+        self.weekly_budget = weekly_budget;
+        self.min_play_count = min_play_count;
+        self.min_like_count = min_like_count;
+        
+        Ok(())
+    }
+}
+
+#[account]
+pub struct ChartRewardPool {
+    pub week: u64,
+    pub total: u64,
+    pub claimed: u64,
+    pub curve: RewardCurve,    // Parameters fixed when the pool was opened
+    pub decay_bps: u16,
+    pub ranks_paid: u8,
+    pub total_weight: u128,
+    pub min_play_count: u64,
+    pub min_like_count: u64,
+    pub claimed_ranks: u64,    // Bitmap of paid ranks
+    pub opened_at: i64,
+    pub opened_by: Pubkey,     // Receives the rent when the pool is closed
+}
+
+impl ChartRewardPool {
+    pub fn reward_for(&self, rank: u8) -> u64 {
+        let weight = chart_reward_weight(self.curve, self.decay_bps, self.ranks_paid, rank);
+        (self.total as u128 * weight / self.total_weight) as u64
+    }
+}
+
+#[account]
+pub struct PlayEpoch {
+    pub epoch: u64,
+    pub batch_index: u16,
+    pub merkle_root: [u8; 32], // Root of individual play receipts
+    pub total_plays: u64,
+    pub track_count: u16,
+    pub settled_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PlayTally {
+    pub track: Pubkey,
+    pub count: u64,
+}
+
+#[account]
+pub struct Like {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub liked_at: i64,
+}
+
+#[account]
+pub struct Franchise {
+    pub track: Pubkey,
+    pub creator: Pubkey,
+    pub share_mint: Pubkey,
+    pub total_shares: u64,       // Fixed supply of the share mint
+    pub shares_sold: u64,
+    pub staked_shares: u64,      // Shares held in positions (earning revenue)
+    pub share_price: u64,
+    pub revenue_share_bps: u16,  // Cut of creator revenue sent to the vault
+    pub acc_revenue_per_share: u128, // Scaled by SHARE_PRECISION
+    pub total_revenue: u64,
+    pub unallocated_revenue: u64, // Revenue of unsold/withdrawn shares, owed to creator
+    pub is_active: bool,
+}
+
+impl Franchise {
+    // Распределение нового дохода по всем долям
+    pub fn accrue(&mut self, amount: u64) {
+        self.acc_revenue_per_share = self
+            .acc_revenue_per_share
+            .checked_add(
+                (amount as u128)
+                    .checked_mul(SHARE_PRECISION)
+                    .unwrap()
+                    .checked_div(self.total_shares as u128)
+                    .unwrap(),
+            )
+            .unwrap();
+        let unallocated = (amount as u128)
+            .checked_mul(self.total_shares.checked_sub(self.staked_shares).unwrap() as u128)
+            .unwrap()
+            .checked_div(self.total_shares as u128)
+            .unwrap() as u64;
+        self.unallocated_revenue = self.unallocated_revenue.checked_add(unallocated).unwrap();
+        self.total_revenue = self.total_revenue.checked_add(amount).unwrap();
+    }
+
+    pub fn reward_debt_for(&self, shares: u64) -> u128 {
+        (shares as u128).checked_mul(self.acc_revenue_per_share).unwrap()
+    }
+
+    // Перенос начисленного дохода позиции в unclaimed
+    pub fn settle_holding(&self, holding: &mut ShareHolding) {
+        let accrued = self.reward_debt_for(holding.shares);
+        let pending = accrued
+            .checked_sub(holding.reward_debt)
+            .unwrap()
+            .checked_div(SHARE_PRECISION)
+            .unwrap() as u64;
+        holding.unclaimed = holding.unclaimed.checked_add(pending).unwrap();
+        holding.reward_debt = accrued;
+    }
+}
+
+#[account]
+pub struct ShareHolding {
+    pub franchise: Pubkey,
+    pub holder: Pubkey,
+    pub shares: u64,
+    pub reward_debt: u128,
+    pub unclaimed: u64,
+    pub total_claimed: u64,
+}
+
+#[account]
+pub struct Album {
+    pub artist: Pubkey,
+    pub album_id: u64,
+    pub title: String,
+    pub price: u64,              // Bundle price for the whole album
+    pub tracks: Vec<AlbumTrack>, // Ordered track list
+    pub is_listed: bool,
+    pub sales_count: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct AlbumTrack {
+    pub track: Pubkey,
+}
+
+#[account]
+pub struct SubscriptionPlan {
+    pub plan_id: u16,
+    pub price: u64,
+    pub duration: i64,       // Access duration in seconds
+    pub is_active: bool,
+}
+
+#[account]
+pub struct Subscription {
+    pub subscriber: Pubkey,
+    pub plan: Pubkey,
+    pub started_at: i64,
+    pub expires_at: i64,
+    pub cancelled: bool,
+    pub total_paid: u64,
+}
+
+#[account]
+pub struct SubscriptionPeriod {
+    pub period: u64,
+    pub pool_amount: u64,    // Subscription revenue after platform fee
+    pub total_plays: u64,    // Plays settled during the period
+    pub distributed_amount: u64,
+    pub finalized: bool,     // Set by the play oracle once all plays are settled
+}
+
+#[account]
+pub struct SubscriptionSchedule {
+    pub amounts: [u64; SUBSCRIPTION_SCHEDULE_PERIODS], // Deferred pool shares, slot = period % size
+    pub deferred_total: u64,                            // Sum of all slots
+}
+
+#[account]
+pub struct PeriodPlays {
+    pub period: u64,
+    pub track: Pubkey,
+    pub plays: u64,          // Plays of the track settled for the period
+}
+
+#[account]
+pub struct SubscriptionClaim {
+    pub period: u64,
+    pub track: Pubkey,
+    pub plays: u64,
+    pub amount: u64,
+}
+
+#[account]
+pub struct RoyaltyAccount {
+    pub creator: Pubkey,
+    pub total_earned: u64,
+    pub last_claim_time: i64,
+    pub unclaimed: u64,
+    pub total_tips: u64,
+    pub tip_count: u64,
+    pub beneficiary: Pubkey, // Receives claims and tips, creator unless redirected
+    pub has_split_sheet: bool, // Tips must be shared through the split sheet
+}
+
+#[account]
+pub struct ArtistProfile {
+    pub creator: Pubkey,
+    pub display_name: String,
+    pub avatar_cid: String,
+    pub socials_hash: [u8; 32],    // Hash of the off-chain socials document
+    pub status: VerificationStatus,
+    pub payout_wallet: Pubkey,     // Receives proceeds of new tracks
+    pub default_royalty: u8,       // Used when create_track gets no royalty
+    pub split_template: Vec<SplitRecipient>, // Copied to the split sheet on request
+    pub track_count: u32,          // Tracks created under this profile
+    pub created_at: i64,
+    pub verified_at: i64,          // 0 unless verified
+    pub bump: u8,
+}
+
+#[account]
+pub struct ArtistNameClaim {
+    pub creator: Pubkey, // Verified artist holding the display name
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationStatus {
+    Unverified,
+    Verified,
+    Suspended,
+}
+
+#[account]
+pub struct SplitSheet {
+    pub creator: Pubkey,
+    pub recipients: Vec<SplitRecipient>, // Remainder goes to the creator
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct SplitRecipient {
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+}
+
+// Events
+#[event]
+pub struct ArtistProfileUpdatedEvent {
+    pub creator: Pubkey,
+    pub display_name: String,
+    pub payout_wallet: Pubkey,
+    pub default_royalty: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArtistVerificationEvent {
+    pub creator: Pubkey,
+    pub status: VerificationStatus,
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackCreatedEvent {
+    pub track: Pubkey,
+    pub creator: Pubkey,
+    pub track_name: String,
+    pub price: u64,
+    pub royalty_percentage: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackPurchasedEvent {
+    pub track: Pubkey,
+    pub buyer: Pubkey,
+    pub artist: Pubkey,
+    pub price: u64,
+    pub royalty_amount: u64,
+    pub platform_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackMigratedEvent {
+    pub track: Pubkey,
+    pub layout_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DropConfiguredEvent {
+    pub track: Pubkey,
+    pub allowlist_root: [u8; 32],
+    pub presale_price: u64,
+    pub max_per_wallet: u32,
+    pub presale_supply: u64,
+    pub allowlist_start: i64,
+    pub allowlist_end: i64,
+    pub public_start: i64,
+    pub public_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FranchiseCreatedEvent {
+    pub track: Pubkey,
+    pub share_mint: Pubkey,
+    pub total_shares: u64,
+    pub share_price: u64,
+    pub revenue_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SharesPurchasedEvent {
+    pub track: Pubkey,
+    pub buyer: Pubkey,
+    pub shares: u64,
+    pub cost: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FranchiseRevenueClaimedEvent {
+    pub track: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AlbumCreatedEvent {
+    pub album: Pubkey,
+    pub artist: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AlbumTrackAddedEvent {
+    pub album: Pubkey,
+    pub track: Pubkey,
+    pub position: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AlbumTrackRemovedEvent {
+    pub album: Pubkey,
+    pub track: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AlbumUpdatedEvent {
+    pub album: Pubkey,
+    pub price: u64,
+    pub is_listed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AlbumPurchasedEvent {
+    pub album: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub individual_total: u64, // Sum of track prices bought separately
+    pub platform_fee: u64,
+    pub track_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackPlayedEvent {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub nonce: u64,
+    pub amount_paid: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamBalanceDepositedEvent {
+    pub listener: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamBalanceWithdrawnEvent {
+    pub listener: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamPriceUpdatedEvent {
+    pub track: Pubkey,
+    pub stream_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPlanUpdatedEvent {
+    pub plan: Pubkey,
+    pub price: u64,
+    pub duration: i64,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscribedEvent {
+    pub subscriber: Pubkey,
+    pub plan: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionCancelledEvent {
+    pub subscriber: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPeriodFinalizedEvent {
+    pub period: u64,
+    pub pool_amount: u64,
+    pub total_plays: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionShareDistributedEvent {
+    pub period: u64,
+    pub track: Pubkey,
+    pub creator: Pubkey,
+    pub plays: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SplitSheetUpdatedEvent {
+    pub creator: Pubkey,
+    pub recipient_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipEvent {
+    pub artist: Pubkey,
+    pub tipper: Pubkey,
+    pub amount: u64,
+    pub platform_fee: u64,
+    pub message_hash: Option<[u8; 32]>,
+    pub total_tips: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamRoyaltiesClaimedEvent {
+    pub creator: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChartRolledOverEvent {
+    pub week: u64,
+    pub entry_count: u64,
+    pub top_track: Pubkey,
+    pub next_week: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChartRewardParamsUpdatedEvent {
+    pub curve: RewardCurve,
+    pub decay_bps: u16,
+    pub ranks_paid: u8,
+    pub weekly_budget: u64,
+    pub min_play_count: u64,
+    pub min_like_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChartRewardsFundedEvent {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChartRewardsOpenedEvent {
+    pub week: u64,
+    pub total: u64,
+    pub ranks_paid: u8,
+    pub curve: RewardCurve,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChartRewardClaimedEvent {
+    pub week: u64,
+    pub rank: u8,
+    pub track: Pubkey,
+    pub creator: Pubkey,
+    pub payout_wallet: Pubkey,
+    pub amount: u64,
+    pub play_count: u64,
+    pub like_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChartRewardsClosedEvent {
+    pub week: u64,
+    pub unclaimed: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlaysSettledEvent {
+    pub epoch: u64,
+    pub batch_index: u16,
+    pub merkle_root: [u8; 32],
+    pub total_plays: u64,
+    pub track_count: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackLikedEvent {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackUnlikedEvent {
+    pub track: Pubkey,
+    pub listener: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceUpdatedEvent {
+    pub track: Pubkey,
+    pub new_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoyaltyUpdatedEvent {
+    pub track: Pubkey,
+    pub new_royalty: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurchaseRefundedEvent {
+    pub track: Pubkey,
+    pub buyer: Pubkey,
+    pub edition: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowReleasedEvent {
+    pub track: Pubkey,
+    pub buyer: Pubkey,
+    pub edition: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimFiledEvent {
+    pub track: Pubkey,
+    pub claimant: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimResolvedEvent {
+    pub track: Pubkey,
+    pub claimant: Pubkey,
+    pub upheld: bool,
+    pub escrowed: u64,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentOracleUpdatedEvent {
+    pub content_oracle: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentRegisteredEvent {
+    pub fingerprint: [u8; 32],
+    pub track: Pubkey,
+    pub creator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EditionTreeCreatedEvent {
+    pub artist: Pubkey,
+    pub index: u32,
+    pub merkle_tree: Pubkey,
+    pub capacity: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompressedEditionMintedEvent {
+    pub merkle_tree: Pubkey,
+    pub leaf_index: u64,
+    pub track: Pubkey,
+    pub owner: Pubkey,
+    pub edition: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RentalTermsUpdatedEvent {
+    pub track: Pubkey,
+    pub rental_price: u64,
+    pub rental_duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackRentedEvent {
+    pub track: Pubkey,
+    pub renter: Pubkey,
+    pub price: u64,
+    pub platform_fee: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BondingCurveConfiguredEvent {
+    pub track: Pubkey,
+    pub kind: CurveKind,
+    pub base_price: u64,
+    pub slope: u64,
+    pub reserve_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CurvePriceUpdatedEvent {
+    pub track: Pubkey,
+    pub supply: u64,
+    pub reserve: u64,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CurveBuybackEvent {
+    pub track: Pubkey,
+    pub seller: Pubkey,
+    pub payout: u64,
+    pub supply: u64,
+    pub reserve: u64,
+    pub price: u64,
+    pub editions_left: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CouponCreatedEvent {
+    pub coupon: Pubkey,
+    pub artist: Pubkey,
+    pub track: Pubkey,
+    pub kind: DiscountKind,
+    pub value: u64,
+    pub max_uses: u32,
+    pub max_uses_per_wallet: u32,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CouponRedeemedEvent {
+    pub coupon: Pubkey,
+    pub track: Pubkey,
+    pub buyer: Pubkey,
+    pub original_price: u64,
+    pub discount: u64,
+    pub uses: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CouponClosedEvent {
+    pub coupon: Pubkey,
+    pub artist: Pubkey,
+    pub uses: u32,
+    pub total_discount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrackGiftedEvent {
+    pub track: Pubkey,
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub price: u64,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LicenseTransferredEvent {
+    pub track: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub editions_left: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LicenseClosedEvent {
+    pub track: Pubkey,
+    pub owner: Pubkey,
+    pub expired_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RemixLicenseGrantedEvent {
+    pub parent_track: Pubkey,
+    pub remixer: Pubkey,
+    pub share_bps: u16,
+    pub max_derivatives: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RemixLicenseRevokedEvent {
+    pub parent_track: Pubkey,
+    pub remixer: Pubkey,
+    pub derivatives: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RemixRegisteredEvent {
+    pub track: Pubkey,
+    pub parent_track: Pubkey,
+    pub parent_creator: Pubkey,
+    pub share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UpstreamRoyaltyPaidEvent {
+    pub track: Pubkey,
+    pub parent_track: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutRedirectedEvent {
+    pub creator: Pubkey,
+    pub target: Pubkey, // Track or RoyaltyAccount
+    pub payout_wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingToggledEvent {
+    pub track: Pubkey,
+    pub is_listed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoyaltiesPaidEvent {
+    pub total_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformFeeUpdatedEvent {
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TipFeeUpdatedEvent {
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlayOracleUpdatedEvent {
+    pub play_oracle: Pubkey,
+    pub min_play_interval: i64,
+    pub max_daily_plays: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawnEvent {
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Contexts
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 8 + 2 + 32 + 32 + 1 + 8 + 32 + 8 + 4 + 8 + 1 + 8 + 2 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"tracknft"],
+        bump
+    )]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"treasury"],
+        bump,
+        token::mint = payment_mint,
+        token::authority = track_nft
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetPlatformFee<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStreamVault<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(address = track_nft.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"stream_vault"],
+        bump,
+        token::mint = payment_mint,
+        token::authority = track_nft
+    )]
+    pub stream_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSaleEscrow<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(address = track_nft.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"sale_escrow"],
+        bump,
+        token::mint = payment_mint,
+        token::authority = track_nft
+    )]
+    pub sale_escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetPlayOracle<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = track_nft.payment_mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    track_name: String,
+    ipfs_hash: String,
+    price: u64,
+    royalty_percentage: Option<u8>,
+    fingerprint: [u8; 32]
+)]
+pub struct CreateTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = authority,
+        space = TRACK_ACCOUNT_SPACE,
+        seeds = [b"track", artist.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
+        bump
+    )]
+    pub track: Account<'info, Track>,
+    #[account(
+        constraint = artist.owner == authority.key() @ ErrorCode::InvalidArtistAccount,
+        constraint = artist.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 8,
+        seeds = [b"content", fingerprint.as_ref()],
+        bump
+    )]
+    pub content_record: Account<'info, ContentRecord>,
+    // Supplies the artist name, default royalty and payout wallet
+    #[account(mut, seeds = [b"artist_profile", authority.key().as_ref()], bump = artist_profile.bump)]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    /// CHECK: ArtistNameClaim of the profile name, empty unless a verified artist holds it
+    #[account(seeds = [b"artist_name", artist_name_hash(&artist_profile.display_name).as_ref()], bump)]
+    pub name_claim: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: instructions sysvar, used to read the content oracle attestation
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == track.payout_wallet @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = buyer_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.payout_wallet @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = LICENSE_ACCOUNT_SPACE,
+        seeds = [b"license", track.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub license: Option<Account<'info, License>>, // Not used for compressed editions
+    // Required only for tracks with a drop
+    #[account(mut, seeds = [b"drop", track.key().as_ref()], bump)]
+    pub drop_config: Option<Account<'info, DropConfig>>,
+    // Required only during the allowlist phase
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + 32 + 32 + 4 + 1,
+        seeds = [b"drop_buyer", track.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub drop_buyer: Option<Account<'info, DropBuyer>>,
+    // Required only for tracks with a franchise
+    #[account(mut, seeds = [b"franchise", track.key().as_ref()], bump)]
+    pub franchise: Option<Account<'info, Franchise>>,
+    #[account(mut, seeds = [b"franchise_vault", track.key().as_ref()], bump)]
+    pub franchise_vault: Option<Account<'info, TokenAccount>>,
+    // Required only for tracks with compressed editions
+    #[account(mut, address = track.edition_tree)]
+    pub edition_tree: Option<Account<'info, EditionTree>>,
+    /// CHECK: Bubblegum tree config of merkle_tree, checked by Bubblegum
+    #[account(mut)]
+    pub tree_config: Option<UncheckedAccount<'info>>,
+    /// CHECK: checked against edition_tree.merkle_tree and by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: SPL account compression program
+    #[account(address = mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: SPL noop program
+    #[account(address = mpl_bubblegum::programs::SPL_NOOP_ID)]
+    pub log_wrapper: Option<UncheckedAccount<'info>>,
+    // Required only while the track is under dispute
+    #[account(mut, seeds = [b"claim", track.key().as_ref()], bump)]
+    pub copyright_claim: Option<Account<'info, CopyrightClaim>>,
+    #[account(mut, seeds = [b"dispute_escrow", track.key().as_ref()], bump)]
+    pub dispute_escrow: Option<Account<'info, TokenAccount>>,
+    // Required only while the refund window is enabled and the track is not curve-priced
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 32 + 8 + 8,
+        seeds = [b"pending_sale", track.key().as_ref(), &(track.sales_count + 1).to_le_bytes()],
+        bump
+    )]
+    pub pending_sale: Option<Account<'info, PendingSale>>,
+    #[account(mut, seeds = [b"sale_escrow"], bump = track_nft.sale_escrow_bump)]
+    pub sale_escrow: Option<Account<'info, TokenAccount>>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+    // Required only for curve-priced tracks
+    #[account(mut, seeds = [b"curve", track.key().as_ref()], bump = bonding_curve.bump)]
+    pub bonding_curve: Option<Account<'info, BondingCurve>>,
+    #[account(mut, seeds = [b"curve_reserve", track.key().as_ref()], bump)]
+    pub curve_reserve: Option<Account<'info, TokenAccount>>,
+    // Optional discount coupon
+    #[account(
+        mut,
+        seeds = [b"coupon", coupon.artist.as_ref(), &coupon.coupon_id.to_le_bytes()],
+        bump = coupon.bump
+    )]
+    pub coupon: Option<Account<'info, Coupon>>,
+    // Required together with the coupon
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + 32 + 32 + 4 + 1,
+        seeds = [b"coupon_redemption", coupon.as_ref().map(|c| c.key()).unwrap_or_default().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub coupon_redemption: Option<Account<'info, CouponRedemption>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GiftTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == track.payout_wallet @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = payer_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.payout_wallet @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = LICENSE_ACCOUNT_SPACE,
+        seeds = [b"license", track.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub license: Option<Account<'info, License>>, // Not used for compressed editions
+    // Required only for tracks with a drop
+    #[account(seeds = [b"drop", track.key().as_ref()], bump)]
+    pub drop_config: Option<Account<'info, DropConfig>>,
+    // Required only for tracks with a franchise
+    #[account(mut, seeds = [b"franchise", track.key().as_ref()], bump)]
+    pub franchise: Option<Account<'info, Franchise>>,
+    #[account(mut, seeds = [b"franchise_vault", track.key().as_ref()], bump)]
+    pub franchise_vault: Option<Account<'info, TokenAccount>>,
+    // Required only for tracks with compressed editions
+    #[account(mut, address = track.edition_tree)]
+    pub edition_tree: Option<Account<'info, EditionTree>>,
+    /// CHECK: Bubblegum tree config of merkle_tree, checked by Bubblegum
+    #[account(mut)]
+    pub tree_config: Option<UncheckedAccount<'info>>,
+    /// CHECK: checked against edition_tree.merkle_tree and by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: SPL account compression program
+    #[account(address = mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: SPL noop program
+    #[account(address = mpl_bubblegum::programs::SPL_NOOP_ID)]
+    pub log_wrapper: Option<UncheckedAccount<'info>>,
+    // Required only while the track is under dispute
+    #[account(mut, seeds = [b"claim", track.key().as_ref()], bump)]
+    pub copyright_claim: Option<Account<'info, CopyrightClaim>>,
+    #[account(mut, seeds = [b"dispute_escrow", track.key().as_ref()], bump)]
+    pub dispute_escrow: Option<Account<'info, TokenAccount>>,
+    // Required only while the refund window is enabled and the track is not curve-priced
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 8 + 32 + 8 + 8,
+        seeds = [b"pending_sale", track.key().as_ref(), &(track.sales_count + 1).to_le_bytes()],
+        bump
+    )]
+    pub pending_sale: Option<Account<'info, PendingSale>>,
+    #[account(mut, seeds = [b"sale_escrow"], bump = track_nft.sale_escrow_bump)]
+    pub sale_escrow: Option<Account<'info, TokenAccount>>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+    pub recipient: SystemAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestRefund<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, address = pending_sale.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"pending_sale", track.key().as_ref(), &pending_sale.edition.to_le_bytes()],
+        bump,
+        constraint = pending_sale.buyer == buyer.key() @ ErrorCode::Unauthorized
+    )]
+    pub pending_sale: Account<'info, PendingSale>,
+    // Not used for compressed editions
+    #[account(mut, seeds = [b"license", track.key().as_ref(), pending_sale.holder.as_ref()], bump)]
+    pub license: Option<Account<'info, License>>,
+    #[account(mut, seeds = [b"sale_escrow"], bump = track_nft.sale_escrow_bump)]
+    pub sale_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = buyer_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    // Required only for compressed editions
+    #[account(address = pending_sale.edition_tree)]
+    pub edition_tree: Option<Account<'info, EditionTree>>,
+    /// CHECK: Bubblegum tree config of merkle_tree, checked by Bubblegum
+    #[account(mut)]
+    pub tree_config: Option<UncheckedAccount<'info>>,
+    /// CHECK: checked against edition_tree.merkle_tree and by Bubblegum
+    #[account(mut)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+    /// CHECK: cNFT owner, checked against the leaf by Bubblegum
+    #[account(address = pending_sale.holder)]
+    pub holder: Option<UncheckedAccount<'info>>,
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: SPL account compression program
+    #[account(address = mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: SPL noop program
+    #[account(address = mpl_bubblegum::programs::SPL_NOOP_ID)]
+    pub log_wrapper: Option<UncheckedAccount<'info>>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseEscrow<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, address = pending_sale.track)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"pending_sale", track.key().as_ref(), &pending_sale.edition.to_le_bytes()],
+        bump
+    )]
+    pub pending_sale: Account<'info, PendingSale>,
+    // Not used for compressed editions
+    #[account(
+        mut,
+        seeds = [b"license", track.key().as_ref(), pending_sale.holder.as_ref()],
+        bump
+    )]
+    pub license: Option<Account<'info, License>>,
+    #[account(mut, seeds = [b"sale_escrow"], bump = track_nft.sale_escrow_bump)]
+    pub sale_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == track.payout_wallet @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_royalties.owner == track.payout_wallet @ ErrorCode::InvalidRoyaltyAccount,
+        constraint = creator_royalties.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_royalties: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    // Required only for tracks with a franchise
+    #[account(mut, seeds = [b"franchise", track.key().as_ref()], bump)]
+    pub franchise: Option<Account<'info, Franchise>>,
+    #[account(mut, seeds = [b"franchise_vault", track.key().as_ref()], bump)]
+    pub franchise_vault: Option<Account<'info, TokenAccount>>,
+    // Required only while the track is under dispute
+    #[account(mut, seeds = [b"claim", track.key().as_ref()], bump)]
+    pub copyright_claim: Option<Account<'info, CopyrightClaim>>,
+    #[account(mut, seeds = [b"dispute_escrow", track.key().as_ref()], bump)]
+    pub dispute_escrow: Option<Account<'info, TokenAccount>>,
+    /// CHECK: buyer wallet, receives the pending sale rent
+    #[account(mut, address = pending_sale.buyer)]
+    pub buyer: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(tree_index: u32)]
+pub struct CreateEditionTree<'info> {
+    #[account(
+        init,
+        payer = artist,
+        space = 8 + 32 + 4 + 32 + 4 + 4 + 8 + 8 + 1,
+        seeds = [b"edition_tree", artist.key().as_ref(), &tree_index.to_le_bytes()],
+        bump
+    )]
+    pub edition_tree: Account<'info, EditionTree>,
+    /// CHECK: Bubblegum tree config of merkle_tree, initialized via CPI
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+    /// CHECK: zeroed account owned by the compression program, initialized via CPI
+    #[account(mut, owner = compression_program.key())]
+    pub merkle_tree: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub artist: Signer<'info>,
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    /// CHECK: SPL account compression program
+    #[account(address = mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    /// CHECK: SPL noop program
+    #[account(address = mpl_bubblegum::programs::SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCompressedEditions<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    // Receives the track's new editions once enabled
+    #[account(
+        seeds = [b"edition_tree", track.creator.as_ref(), &edition_tree.index.to_le_bytes()],
+        bump = edition_tree.bump
+    )]
+    pub edition_tree: Account<'info, EditionTree>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FileClaim<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8,
+        seeds = [b"claim", track.key().as_ref()],
+        bump
+    )]
+    pub copyright_claim: Account<'info, CopyrightClaim>,
+    #[account(
+        init_if_needed,
+        payer = claimant,
+        seeds = [b"dispute_escrow", track.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = track_nft
+    )]
+    pub dispute_escrow: Account<'info, TokenAccount>,
+    #[account(address = track_nft.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == claimant.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = claimant_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveClaim<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(mut, close = claimant, seeds = [b"claim", track.key().as_ref()], bump)]
+    pub copyright_claim: Account<'info, CopyrightClaim>,
+    #[account(mut, seeds = [b"dispute_escrow", track.key().as_ref()], bump)]
+    pub dispute_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = claimant_token_account.owner == copyright_claim.claimant @ ErrorCode::InvalidBuyerAccount,
+        constraint = claimant_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == track.payout_wallet @ ErrorCode::InvalidArtistAccount,
+        constraint = creator_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    // Required when upholding a claim against a track with a registered fingerprint
+    #[account(mut, seeds = [b"content", track.fingerprint.as_ref()], bump)]
+    pub content_record: Option<Account<'info, ContentRecord>>,
+    #[account(mut, address = copyright_claim.claimant)]
+    pub claimant: SystemAccount<'info>,
+    pub arbiter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetRentalTerms<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferLicense<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(mut, seeds = [b"license", track.key().as_ref(), owner.key().as_ref()], bump)]
+    pub license: Account<'info, License>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = LICENSE_ACCOUNT_SPACE,
+        seeds = [b"license", track.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_license: Account<'info, License>,
+    #[account(constraint = recipient.key() != owner.key() @ ErrorCode::InvalidRecipient)]
+    pub recipient: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RentTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == track.payout_wallet @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = buyer_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = LICENSE_ACCOUNT_SPACE,
+        seeds = [b"license", track.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub license: Account<'info, License>,
+    // Required only for tracks with a franchise
+    #[account(mut, seeds = [b"franchise", track.key().as_ref()], bump)]
+    pub franchise: Option<Account<'info, Franchise>>,
+    #[account(mut, seeds = [b"franchise_vault", track.key().as_ref()], bump)]
+    pub franchise_vault: Option<Account<'info, TokenAccount>>,
+    // Required only while the track is under dispute
+    #[account(mut, seeds = [b"claim", track.key().as_ref()], bump)]
+    pub copyright_claim: Option<Account<'info, CopyrightClaim>>,
+    #[account(mut, seeds = [b"dispute_escrow", track.key().as_ref()], bump)]
+    pub dispute_escrow: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct IsAccessValid<'info> {
+    pub track: Account<'info, Track>,
+    /// CHECK: license may not exist yet, it is only read when initialized
+    #[account(seeds = [b"license", track.key().as_ref(), owner.key().as_ref()], bump)]
+    pub license: UncheckedAccount<'info>,
+    /// CHECK: wallet whose access is checked
+    pub owner: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredLicense<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"license", license.track.as_ref(), license.owner.as_ref()],
+        bump
+    )]
+    pub license: Account<'info, License>,
+    #[account(mut, address = license.owner)]
+    pub owner: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(remixer: Pubkey)]
+pub struct GrantRemixLicense<'info> {
+    pub parent_track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 32 + 32 + 2 + 4 + 4 + 8,
+        seeds = [b"remix_license", parent_track.key().as_ref(), remixer.as_ref()],
+        bump
+    )]
+    pub remix_license: Account<'info, RemixLicense>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureBondingCurve<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 8 + 8 + 2 + 8 + 8 + 8 + 1,
+        seeds = [b"curve", track.key().as_ref()],
+        bump
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"curve_reserve", track.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = track_nft
+    )]
+    pub curve_reserve: Account<'info, TokenAccount>,
+    #[account(address = track_nft.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SellToCurve<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(mut, seeds = [b"curve", track.key().as_ref()], bump = bonding_curve.bump)]
+    pub bonding_curve: Account<'info, BondingCurve>,
+    #[account(mut, seeds = [b"curve_reserve", track.key().as_ref()], bump)]
+    pub curve_reserve: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"license", track.key().as_ref(), seller.key().as_ref()], bump)]
+    pub license: Account<'info, License>,
+    #[account(
+        mut,
+        constraint = seller_token_account.owner == seller.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = seller_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(coupon_id: u64)]
+pub struct CreateCoupon<'info> {
+    #[account(
+        init,
+        payer = artist,
+        space = 8 + 32 + 8 + 32 + 1 + 8 + 4 + 4 + 4 + 8 + 32 + 8 + 8 + 1,
+        seeds = [b"coupon", artist.key().as_ref(), &coupon_id.to_le_bytes()],
+        bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+    // Required only for single-track coupons
+    pub track: Option<Account<'info, Track>>,
+    #[account(mut)]
+    pub artist: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCoupon<'info> {
+    #[account(
+        mut,
+        close = artist,
+        seeds = [b"coupon", artist.key().as_ref(), &coupon.coupon_id.to_le_bytes()],
+        bump = coupon.bump
+    )]
+    pub coupon: Account<'info, Coupon>,
+    #[account(mut)]
+    pub artist: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRemixLicense<'info> {
+    pub parent_track: Account<'info, Track>,
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"remix_license", parent_track.key().as_ref(), remix_license.remixer.as_ref()],
+        bump
+    )]
+    pub remix_license: Account<'info, RemixLicense>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureDrop<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 8 + 4 + 8 + 8 + 8 + 8 + 8 + 8,
+        seeds = [b"drop", track.key().as_ref()],
+        bump
+    )]
+    pub drop_config: Account<'info, DropConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDrop<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(mut, close = authority, seeds = [b"drop", track.key().as_ref()], bump)]
+    pub drop_config: Account<'info, DropConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFranchise<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 16 + 8 + 8 + 1,
+        seeds = [b"franchise", track.key().as_ref()],
+        bump
+    )]
+    pub franchise: Account<'info, Franchise>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"share_mint", track.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = track_nft
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"share_vault", track.key().as_ref()],
+        bump,
+        token::mint = share_mint,
+        token::authority = track_nft
+    )]
+    pub share_vault: Account<'info, TokenAccount>,
+    #[account(address = track_nft.payment_mint)]
+    pub payment_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"franchise_vault", track.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = track_nft
+    )]
+    pub franchise_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseShares<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub franchise: Account<'info, Franchise>,
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + 32 + 32 + 8 + 16 + 8 + 8,
+        seeds = [b"holding", franchise.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub holding: Account<'info, ShareHolding>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == franchise.creator @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = buyer_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MoveShares<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub franchise: Account<'info, Franchise>,
+    #[account(mut, seeds = [b"holding", franchise.key().as_ref(), holder.key().as_ref()], bump)]
+    pub holding: Account<'info, ShareHolding>,
+    #[account(mut, seeds = [b"share_vault", franchise.track.as_ref()], bump)]
+    pub share_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = holder_share_account.owner == holder.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = holder_share_account.mint == franchise.share_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub holder_share_account: Account<'info, TokenAccount>,
+    pub holder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFranchiseRevenue<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub franchise: Account<'info, Franchise>,
+    #[account(mut, seeds = [b"holding", franchise.key().as_ref(), holder.key().as_ref()], bump)]
+    pub holding: Account<'info, ShareHolding>,
+    #[account(mut, seeds = [b"franchise_vault", franchise.track.as_ref()], bump)]
+    pub franchise_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = holder_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub holder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimUnallocatedRevenue<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub franchise: Account<'info, Franchise>,
+    #[account(mut, seeds = [b"franchise_vault", franchise.track.as_ref()], bump)]
+    pub franchise_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key() @ ErrorCode::InvalidArtistAccount,
+        constraint = creator_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetFranchiseActive<'info> {
+    #[account(mut)]
+    pub franchise: Account<'info, Franchise>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(album_id: u64)]
+pub struct CreateAlbum<'info> {
+    #[account(
+        init,
+        payer = artist,
+        space = 8 + 32 + 8 + (4 + MAX_ALBUM_TITLE_LEN) + 8 + (4 + MAX_ALBUM_TRACKS * 32) + 1 + 8,
+        seeds = [b"album", artist.key().as_ref(), album_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub album: Account<'info, Album>,
+    #[account(mut)]
+    pub artist: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddAlbumTrack<'info> {
+    #[account(mut)]
+    pub album: Account<'info, Album>,
+    pub track: Account<'info, Track>,
+    pub artist: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAlbum<'info> {
+    #[account(mut)]
+    pub album: Account<'info, Album>,
+    pub artist: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyAlbum<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub album: Account<'info, Album>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == album.artist @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = buyer_token_account.owner == buyer.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = buyer_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTrack<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    /// CHECK: Old-layout Track account; owner is checked and data is migrated manually
+    #[account(mut)]
+    pub track: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterListener<'info> {
+    #[account(
+        init,
+        payer = listener,
+        space = 8 + 32 + 8 + 8 + 8 + 4 + 8 + 8 + 8,
+        seeds = [b"listener", listener.key().as_ref()],
+        bump
+    )]
+    pub listener_state: Account<'info, ListenerState>,
+    #[account(mut)]
+    pub listener: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlayTrack<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    #[account(mut, seeds = [b"listener", listener.key().as_ref()], bump)]
+    pub listener_state: Account<'info, ListenerState>,
+    // Required only for tracks with a stream price
+    #[account(mut, seeds = [b"royalty", track.creator.as_ref()], bump)]
+    pub creator_royalty: Option<Account<'info, RoyaltyAccount>>,
+    // Active subscription waives the stream price
+    #[account(seeds = [b"subscription", listener.key().as_ref()], bump)]
+    pub subscription: Option<Account<'info, Subscription>>,
+    // Required only for paid streams of tracks with a franchise
+    #[account(mut, seeds = [b"franchise", track.key().as_ref()], bump)]
+    pub franchise: Option<Account<'info, Franchise>>,
+    #[account(mut, seeds = [b"franchise_vault", track.key().as_ref()], bump)]
+    pub franchise_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub listener: Signer<'info>,
+    /// CHECK: Instructions sysvar, used to read the ed25519 attestation
+    #[account(address = ix_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositStreamBalance<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"listener", listener.key().as_ref()], bump)]
+    pub listener_state: Account<'info, ListenerState>,
+    #[account(
+        mut,
+        constraint = listener_token_account.owner == listener.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = listener_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub listener_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    pub listener: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStreamBalance<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"listener", listener.key().as_ref()], bump)]
+    pub listener_state: Account<'info, ListenerState>,
+    #[account(
+        mut,
+        constraint = listener_token_account.owner == listener.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = listener_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub listener_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    pub listener: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetStreamPrice<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRoyaltyAccount<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 32 + 1,
+        seeds = [b"royalty", creator.key().as_ref()],
+        bump
+    )]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSplitSheet<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 32 + (4 + MAX_SPLIT_RECIPIENTS * (32 + 2)),
+        seeds = [b"splits", creator.key().as_ref()],
+        bump
+    )]
+    pub split_sheet: Account<'info, SplitSheet>,
+    #[account(mut, seeds = [b"royalty", creator.key().as_ref()], bump)]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSplitSheet<'info> {
+    #[account(mut, seeds = [b"splits", creator.key().as_ref()], bump)]
+    pub split_sheet: Account<'info, SplitSheet>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateArtistProfile<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = creator,
+        space = ARTIST_PROFILE_SPACE,
+        seeds = [b"artist_profile", creator.key().as_ref()],
+        bump
+    )]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    #[account(constraint = payout_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint)]
+    pub payout_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateArtistProfile<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"artist_profile", creator.key().as_ref()], bump = artist_profile.bump)]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    // Optional, replaces the default payout account
+    #[account(constraint = payout_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint)]
+    pub payout_token_account: Option<Account<'info, TokenAccount>>,
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetProfileVerification<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        mut,
+        seeds = [b"artist_profile", artist_profile.creator.as_ref()],
+        bump = artist_profile.bump
+    )]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    // Required when verifying
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 1,
+        seeds = [b"artist_name", artist_name_hash(&artist_profile.display_name).as_ref()],
+        bump
+    )]
+    pub name_claim: Option<Account<'info, ArtistNameClaim>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-// Accounts
-#[account]
-pub struct TrackNftState {
-    pub authority: Pubkey,
-    pub royalty_percentage: u8, // Default royalty percentage
-    pub total_tracks: u64,
+#[derive(Accounts)]
+pub struct ApplySplitTemplate<'info> {
+    #[account(seeds = [b"artist_profile", creator.key().as_ref()], bump = artist_profile.bump)]
+    pub artist_profile: Account<'info, ArtistProfile>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + 32 + (4 + MAX_SPLIT_RECIPIENTS * (32 + 2)),
+        seeds = [b"splits", creator.key().as_ref()],
+        bump
+    )]
+    pub split_sheet: Account<'info, SplitSheet>,
+    #[account(mut, seeds = [b"royalty", creator.key().as_ref()], bump)]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[account]
-pub struct Track {
-    pub track_name: String,
-    pub artist_name: String,
-    pub ipfs_hash: String,
-    pub price: u64,
-    pub royalty_percentage: u8,
-    pub is_listed: bool,
-    pub play_count: u64,
-    pub like_count: u64,
-    pub total_royalties_paid: u64,
-    pub creator: Pubkey,
-    pub mint_time: i64,
+#[derive(Accounts)]
+pub struct TipArtist<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"royalty", royalty_account.creator.as_ref()], bump)]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    #[account(seeds = [b"splits", royalty_account.creator.as_ref()], bump)]
+    pub split_sheet: Option<Account<'info, SplitSheet>>,
+    #[account(
+        mut,
+        constraint = artist_token_account.owner == royalty_account.beneficiary @ ErrorCode::InvalidArtistAccount,
+        constraint = artist_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub artist_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = tipper_token_account.owner == tipper.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = tipper_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub tipper_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    pub tipper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[account]
-pub struct RoyaltyAccount {
-    pub creator: Pubkey,
-    pub total_earned: u64,
-    pub last_claim_time: i64,
+#[derive(Accounts)]
+pub struct ClaimStreamRoyalties<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"royalty", royalty_account.creator.as_ref()], bump)]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == royalty_account.beneficiary @ ErrorCode::InvalidArtistAccount,
+        constraint = creator_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
-// Events
-#[event]
-pub struct TrackCreatedEvent {
-    pub track: Pubkey,
-    pub creator: Pubkey,
-    pub track_name: String,
-    pub price: u64,
-    pub royalty_percentage: u8,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct SweepStreamFees<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"treasury"], bump = track_nft.treasury_bump)]
+    pub treasury: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[event]
-pub struct TrackPurchasedEvent {
-    pub track: Pubkey,
-    pub buyer: Pubkey,
-    pub artist: Pubkey,
-    pub price: u64,
-    pub royalty_amount: u64,
-    pub platform_fee: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+#[instruction(epoch: u64, batch_index: u16)]
+pub struct SettlePlays<'info> {
+    #[account(mut, seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = play_oracle,
+        space = 8 + 8 + 2 + 32 + 8 + 2 + 8,
+        seeds = [b"play_epoch", epoch.to_le_bytes().as_ref(), batch_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub play_epoch: Account<'info, PlayEpoch>,
+    // Optional, plays of epochs outside an open period only count towards the chart
+    #[account(mut)]
+    pub subscription_period: Option<Account<'info, SubscriptionPeriod>>,
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: Option<AccountLoader<'info, Leaderboard>>,
+    #[account(mut, constraint = play_oracle.key() == track_nft.play_oracle @ ErrorCode::Unauthorized)]
+    pub play_oracle: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[event]
-pub struct TrackPlayedEvent {
-    pub track: Pubkey,
-    pub listener: Pubkey,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct InitializeLeaderboard<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<Leaderboard>(),
+        seeds = [b"leaderboard"],
+        bump
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[event]
-pub struct TrackLikedEvent {
-    pub track: Pubkey,
-    pub listener: Pubkey,
-    pub timestamp: i64,
+#[derive(Accounts)]
+#[instruction(week: u64)]
+pub struct RolloverChart<'info> {
+    #[account(mut, seeds = [b"leaderboard"], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<ChartSnapshot>(),
+        seeds = [b"chart_snapshot", week.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub chart_snapshot: AccountLoader<'info, ChartSnapshot>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[event]
-pub struct PriceUpdatedEvent {
-    pub track: Pubkey,
-    pub new_price: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct InitializeChartRewards<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 1 + 2 + 1 + 8 + 8 + 8 + 8 + 8 + 32 + 1 + 1,
+        seeds = [b"chart_reward_config"],
+        bump
+    )]
+    pub reward_config: Account<'info, ChartRewardConfig>,
+    // Награды выплачиваются в NDT, а не в валюте продаж
+    pub ndt_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"chart_reward_vault"],
+        bump,
+        token::mint = ndt_mint,
+        token::authority = track_nft
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-#[event]
-pub struct RoyaltyUpdatedEvent {
-    pub track: Pubkey,
-    pub new_royalty: u8,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct SetChartRewardParams<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"chart_reward_config"], bump = reward_config.bump)]
+    pub reward_config: Account<'info, ChartRewardConfig>,
+    pub authority: Signer<'info>,
 }
 
-#[event]
-pub struct ListingToggledEvent {
-    pub track: Pubkey,
-    pub is_listed: bool,
-    pub timestamp: i64,
+#[derive(Accounts)]
+pub struct FundChartRewards<'info> {
+    #[account(seeds = [b"chart_reward_config"], bump = reward_config.bump)]
+    pub reward_config: Account<'info, ChartRewardConfig>,
+    #[account(mut, seeds = [b"chart_reward_vault"], bump = reward_config.vault_bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    pub funder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[event]
-pub struct RoyaltiesPaidEvent {
-    pub total_amount: u64,
-    pub timestamp: i64,
+#[derive(Accounts)]
+#[instruction(week: u64)]
+pub struct OpenChartRewards<'info> {
+    #[account(mut, seeds = [b"chart_reward_config"], bump = reward_config.bump)]
+    pub reward_config: Account<'info, ChartRewardConfig>,
+    #[account(seeds = [b"chart_reward_vault"], bump = reward_config.vault_bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"chart_snapshot", week.to_le_bytes().as_ref()], bump)]
+    pub chart_snapshot: AccountLoader<'info, ChartSnapshot>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + 8 + 8 + 1 + 2 + 1 + 16 + 8 + 8 + 8 + 8 + 32,
+        seeds = [b"chart_pool", week.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_pool: Account<'info, ChartRewardPool>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-// Contexts
 #[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(init, payer = authority, seeds = [b"tracknft"], bump)]
+#[instruction(week: u64)]
+pub struct ClaimChartReward<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut, seeds = [b"chart_reward_config"], bump = reward_config.bump)]
+    pub reward_config: Account<'info, ChartRewardConfig>,
+    #[account(mut, seeds = [b"chart_reward_vault"], bump = reward_config.vault_bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"chart_snapshot", week.to_le_bytes().as_ref()], bump)]
+    pub chart_snapshot: AccountLoader<'info, ChartSnapshot>,
+    #[account(mut, seeds = [b"chart_pool", week.to_le_bytes().as_ref()], bump)]
+    pub reward_pool: Account<'info, ChartRewardPool>,
+    pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        constraint = payout_token_account.owner == track.payout_wallet @ ErrorCode::InvalidArtistAccount,
+        constraint = payout_token_account.mint == reward_config.ndt_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub payout_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(week: u64)]
+pub struct CloseChartRewards<'info> {
+    #[account(mut, seeds = [b"chart_reward_config"], bump = reward_config.bump)]
+    pub reward_config: Account<'info, ChartRewardConfig>,
+    #[account(
+        mut,
+        close = opened_by,
+        seeds = [b"chart_pool", week.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub reward_pool: Account<'info, ChartRewardPool>,
+    #[account(mut, address = reward_pool.opened_by)]
+    pub opened_by: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: u16)]
+pub struct CreateSubscriptionPlan<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
     pub track_nft: Account<'info, TrackNftState>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 2 + 8 + 8 + 1,
+        seeds = [b"plan", plan_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateTrack<'info> {
+pub struct UpdateSubscriptionPlan<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
+    #[account(mut)]
+    pub plan: Account<'info, SubscriptionPlan>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(period: u64)]
+pub struct OpenSubscriptionPeriod<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"sub_period", period.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub subscription_period: Account<'info, SubscriptionPeriod>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 8 * SUBSCRIPTION_SCHEDULE_PERIODS + 8,
+        seeds = [b"sub_schedule"],
+        bump
+    )]
+    pub subscription_schedule: Account<'info, SubscriptionSchedule>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
     #[account(mut, seeds = [b"tracknft"], bump)]
     pub track_nft: Account<'info, TrackNftState>,
+    pub plan: Account<'info, SubscriptionPlan>,
     #[account(
         init,
-        payer = authority,
-        space = 8 + 32 + 32 + 32 + 200 + 200 + 8 + 1 + 8 + 8 + 8 + 32 + 8,
-        seeds = [b"track", artist.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
+        payer = subscriber,
+        space = 8 + 32 + 32 + 8 + 8 + 1 + 8,
+        seeds = [b"subscription", subscriber.key().as_ref()],
         bump
     )]
-    pub track: Account<'info, Track>,
+    pub subscription: Account<'info, Subscription>,
     #[account(mut)]
-    pub artist: Account<'info, TokenAccount>,
-    pub authority: Signer<'info>,
+    pub subscription_period: Account<'info, SubscriptionPeriod>,
+    #[account(mut, seeds = [b"sub_schedule"], bump)]
+    pub subscription_schedule: Account<'info, SubscriptionSchedule>,
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = subscriber_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct BuyTrack<'info> {
+pub struct RenewSubscription<'info> {
     #[account(mut, seeds = [b"tracknft"], bump)]
     pub track_nft: Account<'info, TrackNftState>,
+    pub plan: Account<'info, SubscriptionPlan>,
+    #[account(mut, seeds = [b"subscription", subscriber.key().as_ref()], bump)]
+    pub subscription: Account<'info, Subscription>,
     #[account(mut)]
-    pub track: Account<'info, Track>,
-    #[account(mut)]
-    pub artist: Account<'info, TokenAccount>,
+    pub subscription_period: Account<'info, SubscriptionPeriod>,
+    #[account(mut, seeds = [b"sub_schedule"], bump)]
+    pub subscription_schedule: Account<'info, SubscriptionSchedule>,
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ ErrorCode::InvalidBuyerAccount,
+        constraint = subscriber_token_account.mint == track_nft.payment_mint @ ErrorCode::InvalidPaymentMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"stream_vault"], bump = track_nft.stream_vault_bump)]
+    pub stream_vault: Account<'info, TokenAccount>,
+    pub subscriber: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(mut, seeds = [b"subscription", subscriber.key().as_ref()], bump)]
+    pub subscription: Account<'info, Subscription>,
+    pub subscriber: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSubscriptionPeriod<'info> {
+    #[account(seeds = [b"tracknft"], bump)]
+    pub track_nft: Account<'info, TrackNftState>,
     #[account(mut)]
-    pub buyer: Account<'info, TokenAccount>,
+    pub subscription_period: Account<'info, SubscriptionPeriod>,
+    #[account(constraint = play_oracle.key() == track_nft.play_oracle @ ErrorCode::Unauthorized)]
+    pub play_oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeSubscriptionShare<'info> {
     #[account(mut)]
-    pub creator_royalties: Account<'info, TokenAccount>,
+    pub subscription_period: Account<'info, SubscriptionPeriod>,
+    pub track: Account<'info, Track>,
+    #[account(
+        seeds = [b"period_plays", subscription_period.period.to_le_bytes().as_ref(), track.key().as_ref()],
+        bump
+    )]
+    pub period_plays: Account<'info, PeriodPlays>,
+    #[account(mut, seeds = [b"royalty", track.creator.as_ref()], bump)]
+    pub creator_royalty: Account<'info, RoyaltyAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + 32 + 8 + 8,
+        seeds = [b"sub_claim", subscription_period.period.to_le_bytes().as_ref(), track.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, SubscriptionClaim>,
     #[account(mut)]
-    pub platform_fees: Account<'info, TokenAccount>,
-    pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct PlayTrack<'info> {
+pub struct VerifyPlayReceipt<'info> {
+    pub play_epoch: Account<'info, PlayEpoch>,
+}
+
+#[derive(Accounts)]
+pub struct LikeTrack<'info> {
     #[account(mut)]
     pub track: Account<'info, Track>,
+    #[account(
+        init,
+        payer = listener,
+        space = 8 + 32 + 32 + 8,
+        seeds = [b"like", track.key().as_ref(), listener.key().as_ref()],
+        bump
+    )]
+    pub like: Account<'info, Like>,
+    #[account(mut)]
     pub listener: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct LikeTrack<'info> {
+pub struct UnlikeTrack<'info> {
     #[account(mut)]
     pub track: Account<'info, Track>,
+    #[account(
+        mut,
+        close = listener,
+        seeds = [b"like", track.key().as_ref(), listener.key().as_ref()],
+        bump
+    )]
+    pub like: Account<'info, Like>,
+    #[account(mut)]
     pub listener: Signer<'info>,
 }
 
@@ -438,6 +7514,22 @@ pub struct UpdateRoyalty<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct RedirectTrackPayout<'info> {
+    #[account(mut)]
+    pub track: Account<'info, Track>,
+    // PDA [b"memorial", track.creator] of the memorial program
+    pub memorial: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedirectCreatorRoyalties<'info> {
+    #[account(mut, seeds = [b"royalty", royalty_account.creator.as_ref()], bump)]
+    pub royalty_account: Account<'info, RoyaltyAccount>,
+    // PDA [b"memorial", royalty_account.creator] of the memorial program
+    pub memorial: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ToggleListing<'info> {
     #[account(mut)]
@@ -466,4 +7558,222 @@ pub enum ErrorCode {
     CreatorCannotBuy,
     #[msg("Royalty percentage too high (max 50%)")]
     RoyaltyTooHigh,
+    #[msg("Platform fee too high (max 10%)")]
+    PlatformFeeTooHigh,
+    #[msg("Insufficient funds in treasury")]
+    InsufficientTreasuryFunds,
+    #[msg("Token account mint does not match the payment mint")]
+    InvalidPaymentMint,
+    #[msg("Artist payout account is not owned by the payout wallet")]
+    InvalidArtistAccount,
+    #[msg("Royalty account is not owned by the track creator")]
+    InvalidRoyaltyAccount,
+    #[msg("Buyer token account is not owned by the signer")]
+    InvalidBuyerAccount,
+    #[msg("Play oracle is not configured")]
+    PlayOracleNotSet,
+    #[msg("Invalid play limits")]
+    InvalidPlayLimits,
+    #[msg("Missing ed25519 attestation instruction")]
+    MissingAttestation,
+    #[msg("Attestation does not match the expected signer or message")]
+    InvalidAttestation,
+    #[msg("Attestation nonce already used")]
+    NonceAlreadyUsed,
+    #[msg("Listener play rate limit exceeded")]
+    PlayRateLimited,
+    #[msg("Invalid play settlement batch")]
+    InvalidSettlementBatch,
+    #[msg("Play epoch has not finished yet")]
+    EpochNotFinished,
+    #[msg("Play receipt is not included in the settled batch")]
+    InvalidPlayProof,
+    #[msg("Account is not a track owned by this program")]
+    InvalidTrackAccount,
+    #[msg("Track is already migrated")]
+    TrackAlreadyMigrated,
+    #[msg("Invalid migration data")]
+    InvalidMigrationData,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Insufficient prepaid stream balance")]
+    InsufficientStreamBalance,
+    #[msg("Creator royalty ledger is required for paid streams")]
+    MissingRoyaltyLedger,
+    #[msg("No royalties to claim")]
+    NoRoyaltiesToClaim,
+    #[msg("Invalid subscription plan")]
+    InvalidSubscriptionPlan,
+    #[msg("Subscription plan is not active")]
+    SubscriptionPlanInactive,
+    #[msg("Subscription is already cancelled")]
+    SubscriptionCancelled,
+    #[msg("Subscription period does not match")]
+    InvalidSubscriptionPeriod,
+    #[msg("Subscription is prepaid too far ahead")]
+    SubscriptionPrepaidTooFar,
+    #[msg("Subscription period has not finished")]
+    SubscriptionPeriodNotFinished,
+    #[msg("Subscription period is already finalized")]
+    SubscriptionPeriodFinalized,
+    #[msg("Track has no settled plays in this period")]
+    NoPlaysInPeriod,
+    #[msg("Plays belong to a period before the track's latest settled period")]
+    StalePlayPeriod,
+    #[msg("Album title too long")]
+    AlbumTitleTooLong,
+    #[msg("Album track list is full")]
+    AlbumFull,
+    #[msg("Album has no tracks")]
+    AlbumEmpty,
+    #[msg("Album price must be below the sum of its track prices")]
+    AlbumPriceTooHigh,
+    #[msg("Track is already in the album")]
+    TrackAlreadyInAlbum,
+    #[msg("Invalid album track position")]
+    InvalidAlbumPosition,
+    #[msg("Accounts do not match the album track list")]
+    InvalidAlbumAccounts,
+    #[msg("Invalid license account")]
+    InvalidLicenseAccount,
+    #[msg("Invalid split sheet")]
+    InvalidSplitSheet,
+    #[msg("Accounts do not match the split sheet")]
+    InvalidSplitAccounts,
+    #[msg("Artist tips are shared through a split sheet")]
+    MissingSplitSheet,
+    #[msg("Platform fee cannot be below the tip fee")]
+    PlatformFeeBelowTipFee,
+    #[msg("Drop config is required for this track")]
+    MissingDropConfig,
+    #[msg("Invalid drop schedule")]
+    InvalidDropSchedule,
+    #[msg("No drop phase is open")]
+    DropPhaseClosed,
+    #[msg("Wallet is not on the allowlist")]
+    NotOnAllowlist,
+    #[msg("Per-wallet cap reached")]
+    WalletCapReached,
+    #[msg("Presale supply is sold out")]
+    PresaleSoldOut,
+    #[msg("Franchise accounts are required for this track")]
+    MissingFranchiseAccounts,
+    #[msg("Invalid franchise parameters")]
+    InvalidFranchiseParams,
+    #[msg("Franchise is not active")]
+    FranchiseInactive,
+    #[msg("Not enough shares")]
+    InsufficientShares,
+    #[msg("Tracks with a franchise cannot be sold in albums")]
+    FranchiseTrackInAlbum,
+    #[msg("Track payouts are redirected away from the album artist")]
+    PayoutRedirected,
+    #[msg("Remix license does not match the parent track and remixer")]
+    InvalidRemixLicense,
+    #[msg("Remix parent accounts do not match the track lineage")]
+    InvalidRemixAccounts,
+    #[msg("Too many parent tracks")]
+    TooManyRemixParents,
+    #[msg("Upstream royalty share is too high")]
+    UpstreamShareTooHigh,
+    #[msg("Remixes with upstream royalties cannot be sold in albums")]
+    RemixTrackInAlbum,
+    #[msg("Remix license has no derivatives left")]
+    RemixLicenseExhausted,
+    #[msg("Invalid rental terms")]
+    InvalidRentalTerms,
+    #[msg("Track is not available for rent")]
+    RentalsDisabled,
+    #[msg("Wallet already owns this track")]
+    AlreadyLicensed,
+    #[msg("License has not expired")]
+    LicenseNotExpired,
+    #[msg("Edition tree accounts are required for this track")]
+    MissingEditionTree,
+    #[msg("Invalid edition tree")]
+    InvalidEditionTree,
+    #[msg("Edition tree is full")]
+    EditionTreeFull,
+    #[msg("Content oracle is not configured")]
+    ContentOracleNotSet,
+    #[msg("Audio with this fingerprint is already registered")]
+    DuplicateContent,
+    #[msg("Invalid dispute parameters")]
+    InvalidDisputeParams,
+    #[msg("Dispute accounts are required while the track is under dispute")]
+    MissingDisputeAccounts,
+    #[msg("Track is under a copyright dispute")]
+    TrackUnderDispute,
+    #[msg("Refund window is too long")]
+    InvalidRefundWindow,
+    #[msg("Refund escrow accounts are required while the refund window is enabled")]
+    MissingRefundEscrow,
+    #[msg("Refund window has closed")]
+    RefundWindowClosed,
+    #[msg("Refund window is still open")]
+    RefundWindowOpen,
+    #[msg("Invalid gift or transfer recipient")]
+    InvalidRecipient,
+    #[msg("Gift message is too long")]
+    GiftMessageTooLong,
+    #[msg("Licenses of this track are not transferable")]
+    LicenseNotTransferable,
+    #[msg("No perpetual license edition to give up")]
+    LicenseNotOwned,
+    #[msg("Invalid coupon")]
+    InvalidCoupon,
+    #[msg("Coupon has expired")]
+    CouponExpired,
+    #[msg("Coupon has no uses left")]
+    CouponExhausted,
+    #[msg("Invalid coupon code")]
+    InvalidCouponCode,
+    #[msg("Coupon redemption account is required")]
+    MissingCouponRedemption,
+    #[msg("Wallet has used up its coupon redemptions")]
+    CouponWalletLimit,
+    #[msg("Invalid bonding curve parameters")]
+    InvalidBondingCurve,
+    #[msg("Bonding curve accounts are required for curve-priced tracks")]
+    MissingBondingCurve,
+    #[msg("Track price follows a bonding curve")]
+    CurvePricedTrack,
+    #[msg("Curve reserve has nothing to buy back")]
+    CurveReserveEmpty,
+    #[msg("Leaderboard is not on this chart week")]
+    InvalidChartWeek,
+    #[msg("Chart week has not finished yet")]
+    ChartWeekNotFinished,
+    #[msg("Invalid chart reward parameters")]
+    InvalidChartRewardParams,
+    #[msg("No chart rewards to distribute this week")]
+    NoChartRewards,
+    #[msg("Track does not hold this chart rank")]
+    InvalidChartRank,
+    #[msg("Chart reward already claimed")]
+    ChartRewardClaimed,
+    #[msg("Track does not meet the play or like minimum")]
+    TrackNotEligible,
+    #[msg("Chart reward claim window has closed")]
+    ChartRewardsExpired,
+    #[msg("Chart reward claim window is still open")]
+    ChartRewardsActive,
+    #[msg("Invalid artist profile")]
+    InvalidArtistProfile,
+    #[msg("Artist profile is suspended")]
+    ArtistProfileSuspended,
+    #[msg("Display name belongs to a verified artist")]
+    ArtistNameTaken,
+    #[msg("Artist name claim account is required")]
+    MissingArtistNameClaim,
+    #[msg("Track was taken down after an upheld copyright claim")]
+    TrackTakenDown,
+    #[msg("Content record of the track is required")]
+    MissingContentRecord,
+    #[msg("License has editions still within the refund window")]
+    LicenseRefundPending,
+    #[msg("Account is not used by this purchase")]
+    UnexpectedAccount,
+    #[msg("Drop wallet caps require licenses, not compressed editions")]
+    CompressedDropUnsupported,
 }
\ No newline at end of file